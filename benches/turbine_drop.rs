@@ -0,0 +1,175 @@
+//! Headless stress test for dropping many wind turbines.
+//!
+//! Runs the physics simulation without a window or renderer, spawns `TURBINES`
+//! turbines spread evenly over `FRAMES` frames and reports frame time percentiles
+//! along with entity and asset counts.
+//!
+//! ```sh
+//! cargo bench --bench turbine_drop -- [TURBINES] [FRAMES] [SEED]
+//! ```
+
+use std::time::{Duration, Instant};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use miniature_potato::terrain::{
+    setup_terrain, stream_terrain_chunks, swap_terrain_collider, TerrainConfig,
+};
+use miniature_potato::turbine::{
+    rotate_blades, spawn_wind_turbine, TurbineAssets, TurbineConfig, TurbineEnergy, Wind,
+};
+
+const DEFAULT_TURBINES: u32 = 200;
+const DEFAULT_FRAMES: u32 = 600;
+const DEFAULT_SEED: u64 = 42;
+
+/// Half the side length of the square area turbines are dropped into.
+const DROP_AREA: f32 = 45.0;
+
+#[derive(Resource)]
+struct DropSchedule {
+    turbines: u32,
+    frames: u32,
+    frame: u32,
+    rng: StdRng,
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to the binary, so only look at positional args.
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let turbines = args.first().and_then(|arg| arg.parse().ok());
+    let frames = args.get(1).and_then(|arg| arg.parse().ok());
+    let seed = args.get(2).and_then(|arg| arg.parse().ok());
+
+    let turbines = turbines.unwrap_or(DEFAULT_TURBINES);
+    let frames = frames.unwrap_or(DEFAULT_FRAMES).max(1);
+    let seed = seed.unwrap_or(DEFAULT_SEED);
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        PhysicsPlugins::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Image>()
+    .init_resource::<TerrainConfig>()
+    .init_resource::<TurbineConfig>()
+    .init_resource::<TurbineAssets>()
+    .init_resource::<Wind>()
+    .init_resource::<TurbineEnergy>()
+    .insert_resource(DropSchedule {
+        turbines,
+        frames,
+        frame: 0,
+        rng: StdRng::seed_from_u64(seed),
+    })
+    .add_systems(Startup, setup_terrain)
+    .add_systems(
+        Update,
+        (
+            drop_turbines,
+            rotate_blades,
+            stream_terrain_chunks,
+            swap_terrain_collider,
+        ),
+    );
+
+    app.finish();
+    app.cleanup();
+
+    let mut frame_times = Vec::with_capacity(frames as usize);
+    for _ in 0..frames {
+        let start = Instant::now();
+        app.update();
+        frame_times.push(start.elapsed());
+    }
+
+    report(&mut app, turbines, seed, &mut frame_times);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn drop_turbines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    config: Res<TurbineConfig>,
+    mut turbine_assets: ResMut<TurbineAssets>,
+    mut schedule: ResMut<DropSchedule>,
+) {
+    if schedule.frame >= schedule.frames {
+        return;
+    }
+
+    // Spread the drops evenly so the total matches exactly after the last frame.
+    let frame = schedule.frame as u64;
+    let turbines = schedule.turbines as u64;
+    let frames = schedule.frames as u64;
+    let count = (frame + 1) * turbines / frames - frame * turbines / frames;
+    schedule.frame += 1;
+
+    let material =
+        turbine_assets.material(&config.material, &mut materials, &mut images, &asset_server);
+    let turbine_meshes = turbine_assets.meshes(&config.shape, &mut meshes);
+
+    for _ in 0..count {
+        let position = Vec3::new(
+            schedule.rng.gen_range(-DROP_AREA..DROP_AREA),
+            0.0,
+            schedule.rng.gen_range(-DROP_AREA..DROP_AREA),
+        );
+        let rotation_speed = schedule.rng.gen_range(0.5..1.5);
+
+        spawn_wind_turbine(
+            &mut commands,
+            &turbine_meshes,
+            material.clone(),
+            None,
+            Transform::from_translation(position),
+            rotation_speed,
+            &config.shape,
+            config.blade_count,
+        );
+    }
+}
+
+fn report(app: &mut App, turbines: u32, seed: u64, frame_times: &mut [Duration]) {
+    frame_times.sort_unstable();
+    let percentile = |p: f64| {
+        let index = ((frame_times.len() - 1) as f64 * p).round() as usize;
+        frame_times[index].as_secs_f64() * 1000.0
+    };
+    let total: Duration = frame_times.iter().sum();
+
+    let world = app.world();
+    println!(
+        "turbines: {turbines}, frames: {}, seed: {seed}",
+        frame_times.len()
+    );
+    println!(
+        "frame time (ms): mean {:.3}, p50 {:.3}, p90 {:.3}, p99 {:.3}, max {:.3}",
+        total.as_secs_f64() * 1000.0 / frame_times.len() as f64,
+        percentile(0.5),
+        percentile(0.9),
+        percentile(0.99),
+        percentile(1.0),
+    );
+    println!("entities: {}", world.entities().len());
+    println!(
+        "assets: {} meshes, {} materials, {} images",
+        world.resource::<Assets<Mesh>>().len(),
+        world.resource::<Assets<StandardMaterial>>().len(),
+        world.resource::<Assets<Image>>().len(),
+    );
+}
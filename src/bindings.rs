@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+
+/// Keys for the sandbox's controls, grouped by what they're for. Each control fires on
+/// any of its keys, and an empty list disables it.
+///
+/// The app initializes this once, and the plugins whose systems read it expect it to
+/// be there.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct KeyBindings {
+    pub movement: MovementBindings,
+    pub world: WorldBindings,
+    pub interface: InterfaceBindings,
+    pub debug: DebugBindings,
+}
+
+#[derive(Debug, Clone)]
+pub struct MovementBindings {
+    pub forward: Vec<KeyCode>,
+    pub back: Vec<KeyCode>,
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub jump: Vec<KeyCode>,
+    /// Speeds up walking while held.
+    pub sprint: Vec<KeyCode>,
+    pub crouch: Vec<KeyCode>,
+    /// Slams down onto the ground while in the air.
+    pub ground_slam: Vec<KeyCode>,
+    /// Raises the camera.
+    pub grow: Vec<KeyCode>,
+    /// Lowers the camera.
+    pub shrink: Vec<KeyCode>,
+}
+
+impl Default for MovementBindings {
+    fn default() -> Self {
+        Self {
+            forward: vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            back: vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            left: vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+            right: vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            jump: vec![KeyCode::Backspace],
+            sprint: vec![KeyCode::ShiftLeft],
+            crouch: vec![KeyCode::ControlLeft],
+            ground_slam: vec![KeyCode::KeyQ],
+            grow: vec![KeyCode::Equal],
+            shrink: vec![KeyCode::Minus],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorldBindings {
+    /// Drops a ring of turbines around the player.
+    pub turbine_ring: Vec<KeyCode>,
+    /// Removes the most recently dropped turbine.
+    pub remove_turbine: Vec<KeyCode>,
+    pub reset: Vec<KeyCode>,
+    /// Replaces the terrain with a new one from a random seed.
+    pub regenerate_terrain: Vec<KeyCode>,
+    /// Toggles the breadcrumb trail.
+    pub trail: Vec<KeyCode>,
+}
+
+impl Default for WorldBindings {
+    fn default() -> Self {
+        Self {
+            turbine_ring: vec![KeyCode::KeyG],
+            remove_turbine: vec![KeyCode::KeyX],
+            reset: vec![KeyCode::KeyR],
+            regenerate_terrain: vec![KeyCode::KeyN],
+            trail: vec![KeyCode::KeyB],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceBindings {
+    pub console: Vec<KeyCode>,
+    pub hud: Vec<KeyCode>,
+    pub photo_mode: Vec<KeyCode>,
+    /// Switches between the first and third-person cameras.
+    pub third_person: Vec<KeyCode>,
+    /// Releases the mouse cursor, or grabs it again for mouse look.
+    pub cursor_grab: Vec<KeyCode>,
+    /// Toggles distance fog.
+    pub fog: Vec<KeyCode>,
+    /// Pauses or resumes the game.
+    pub pause: Vec<KeyCode>,
+    /// Pauses the game, and quits when pressed again while paused. See
+    /// `PauseConfig::confirm_quit`.
+    pub quit: Vec<KeyCode>,
+}
+
+impl Default for InterfaceBindings {
+    fn default() -> Self {
+        Self {
+            console: vec![KeyCode::Backquote],
+            hud: vec![KeyCode::F1],
+            photo_mode: vec![KeyCode::F2],
+            third_person: vec![KeyCode::F5],
+            cursor_grab: vec![KeyCode::Tab],
+            fog: vec![KeyCode::F8],
+            pause: vec![KeyCode::KeyP],
+            quit: vec![KeyCode::Escape],
+        }
+    }
+}
+
+/// Toggles for the debug overlays, grouped so they can be switched off together.
+#[derive(Debug, Clone)]
+pub struct DebugBindings {
+    /// When false, none of the debug keys do anything.
+    pub enabled: bool,
+    /// Toggles drawing of contact normals.
+    pub contact_gizmos: Vec<KeyCode>,
+    /// Cycles through the physics gizmo color presets.
+    pub physics_gizmo_preset: Vec<KeyCode>,
+    /// Cycles through tonemappers.
+    pub tonemapping: Vec<KeyCode>,
+    /// Wakes every sleeping rigid body.
+    pub wake_bodies: Vec<KeyCode>,
+}
+
+impl Default for DebugBindings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            contact_gizmos: vec![KeyCode::F3],
+            physics_gizmo_preset: vec![KeyCode::F4],
+            tonemapping: vec![KeyCode::F6],
+            wake_bodies: vec![KeyCode::F7],
+        }
+    }
+}
+
+impl DebugBindings {
+    /// Whether one of `keys` was just pressed and the debug keys are enabled.
+    pub fn just_pressed(&self, input: &ButtonInput<KeyCode>, keys: &[KeyCode]) -> bool {
+        self.enabled && input.any_just_pressed(keys.iter().copied())
+    }
+}
+
+impl KeyBindings {
+    /// On-screen help listing every bound control, by group.
+    pub fn instructions(&self) -> String {
+        let movement = &self.movement;
+        let world = &self.world;
+        let interface = &self.interface;
+        let debug = &self.debug;
+
+        let mut groups = vec![
+            (
+                "Movement",
+                vec![
+                    ("move forward", &movement.forward),
+                    ("move back", &movement.back),
+                    ("move left", &movement.left),
+                    ("move right", &movement.right),
+                    ("jump", &movement.jump),
+                    ("sprint", &movement.sprint),
+                    ("crouch", &movement.crouch),
+                    ("slam down from the air", &movement.ground_slam),
+                    ("get taller", &movement.grow),
+                    ("get shorter", &movement.shrink),
+                ],
+            ),
+            (
+                "World",
+                vec![
+                    ("drop a ring of turbines", &world.turbine_ring),
+                    ("remove the last dropped turbine", &world.remove_turbine),
+                    ("reset the world", &world.reset),
+                    ("generate new terrain", &world.regenerate_terrain),
+                    ("toggle the breadcrumb trail", &world.trail),
+                ],
+            ),
+            (
+                "Interface",
+                vec![
+                    ("open the console", &interface.console),
+                    ("hide the HUD", &interface.hud),
+                    ("photo mode", &interface.photo_mode),
+                    ("toggle the third-person camera", &interface.third_person),
+                    ("release or grab the mouse", &interface.cursor_grab),
+                    ("toggle fog", &interface.fog),
+                    ("pause", &interface.pause),
+                    ("pause, then quit", &interface.quit),
+                ],
+            ),
+        ];
+        if debug.enabled {
+            groups.push((
+                "Debug",
+                vec![
+                    ("show contact normals", &debug.contact_gizmos),
+                    ("cycle physics gizmo colors", &debug.physics_gizmo_preset),
+                    ("cycle tonemapping", &debug.tonemapping),
+                    ("wake all sleeping bodies", &debug.wake_bodies),
+                ],
+            ));
+        }
+
+        let mut text = String::from(
+            "Move the camera with your mouse. Use the scroll-wheel to change the FOV.\n",
+        );
+        for (name, controls) in groups {
+            let lines: Vec<String> = controls
+                .into_iter()
+                .filter(|(_, keys)| !keys.is_empty())
+                .map(|(description, keys)| {
+                    let keys: Vec<String> = keys.iter().map(|key| key_name(*key)).collect();
+                    format!("  {}: {description}", keys.join("/"))
+                })
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+
+            text.push_str(name);
+            text.push('\n');
+            for line in lines {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+
+        text.truncate(text.trim_end().len());
+        text
+    }
+}
+
+/// A short, readable name for `key`.
+pub fn key_name(key: KeyCode) -> String {
+    let name = match key {
+        KeyCode::Backquote => "`",
+        KeyCode::Minus => "-",
+        KeyCode::Equal => "+",
+        KeyCode::BracketLeft => "[",
+        KeyCode::BracketRight => "]",
+        KeyCode::Backslash => "\\",
+        KeyCode::Semicolon => ";",
+        KeyCode::Quote => "'",
+        KeyCode::Comma => ",",
+        KeyCode::Period => ".",
+        KeyCode::Slash => "/",
+        KeyCode::ArrowUp => "Up",
+        KeyCode::ArrowDown => "Down",
+        KeyCode::ArrowLeft => "Left",
+        KeyCode::ArrowRight => "Right",
+        _ => {
+            let name = format!("{key:?}");
+            return name
+                .strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_string();
+        }
+    };
+    name.to_string()
+}
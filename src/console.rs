@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use avian3d::prelude::*;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::bindings::KeyBindings;
+use crate::physics::{CcdConfig, ColliderMarginConfig};
+use crate::terrain::TerrainConfig;
+use crate::turbine::{TurbineConfig, TurbineRingConfig, Wind};
+
+/// Number of output lines kept in the console scrollback.
+const CONSOLE_HISTORY_LINES: usize = 12;
+
+/// Registers a console variable backed by a field of a resource, which must
+/// implement `Display` and `FromStr`.
+#[macro_export]
+macro_rules! console_field {
+    ($app:expr, $name:literal, $resource:ty, $($field:ident).+) => {
+        $crate::console::RegisterConsoleVar::register_console_var(
+            $app,
+            $name,
+            |world| Some(world.get_resource::<$resource>()?.$($field).+.to_string()),
+            |world, value| {
+                world.resource_mut::<$resource>().$($field).+ =
+                    $crate::console::parse_value(value)?;
+                Ok(())
+            },
+        )
+    };
+}
+
+/// A minimal developer console for reading and tweaking config resources.
+///
+/// Supports `get <var>`, `set <var> <value>` and `list`, where variables are
+/// registered by the plugins that own them via [`RegisterConsoleVar`].
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleVars>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(
+                PreUpdate,
+                (console_input, run_console_commands, update_console_text)
+                    .chain()
+                    .after(InputSystem),
+            );
+
+        // Physics settings don't have a plugin of their own, so register them here.
+        app.register_console_var(
+            "gravity.strength",
+            |world| Some(world.get_resource::<Gravity>()?.0.length().to_string()),
+            |world, value| {
+                let strength: f32 = parse_value(value)?;
+                let mut gravity = world.resource_mut::<Gravity>();
+                let direction = gravity.0.try_normalize().unwrap_or(Vec3::NEG_Y);
+                gravity.0 = direction * strength;
+                Ok(())
+            },
+        );
+
+        console_field!(app, "ccd.turbines", CcdConfig, turbines);
+        console_field!(app, "ccd.player", CcdConfig, player);
+        console_field!(
+            app,
+            "margins.player.collision",
+            ColliderMarginConfig,
+            player.collision
+        );
+        console_field!(
+            app,
+            "margins.player.speculative",
+            ColliderMarginConfig,
+            player.speculative
+        );
+        console_field!(
+            app,
+            "margins.terrain.collision",
+            ColliderMarginConfig,
+            terrain.collision
+        );
+        console_field!(
+            app,
+            "margins.terrain.speculative",
+            ColliderMarginConfig,
+            terrain.speculative
+        );
+
+        // Nor does the terrain.
+        console_field!(app, "terrain.size", TerrainConfig, size);
+        console_field!(app, "terrain.view_distance", TerrainConfig, view_distance);
+        console_field!(app, "terrain.scale", TerrainConfig, scale);
+        console_field!(app, "terrain.seed", TerrainConfig, noise.seed);
+        console_field!(app, "terrain.octaves", TerrainConfig, noise.octaves);
+        console_field!(app, "terrain.lacunarity", TerrainConfig, noise.lacunarity);
+        console_field!(app, "terrain.persistence", TerrainConfig, noise.persistence);
+        console_field!(app, "terrain.amplitude", TerrainConfig, noise.amplitude);
+        console_field!(app, "terrain.sand_level", TerrainConfig, bands.sand);
+        console_field!(app, "terrain.grass_level", TerrainConfig, bands.grass);
+        console_field!(app, "terrain.rock_level", TerrainConfig, bands.rock);
+        console_field!(app, "terrain.snow_level", TerrainConfig, bands.snow);
+        console_field!(app, "terrain.sea_level", TerrainConfig, sea_level);
+        console_field!(app, "terrain.smooth_normals", TerrainConfig, smooth_normals);
+
+        // Turbines don't have a plugin either.
+        console_field!(app, "turbine.blade_count", TurbineConfig, blade_count);
+        console_field!(app, "turbine.max_count", TurbineConfig, max_turbines);
+        console_field!(
+            app,
+            "turbine.tower_radius",
+            TurbineConfig,
+            shape.tower_radius
+        );
+        console_field!(
+            app,
+            "turbine.tower_height",
+            TurbineConfig,
+            shape.tower_height
+        );
+        console_field!(
+            app,
+            "turbine.blade_length",
+            TurbineConfig,
+            shape.blade_length
+        );
+        console_field!(
+            app,
+            "turbine.blade_thickness",
+            TurbineConfig,
+            shape.blade_thickness
+        );
+        // `none` builds turbines from the primitive shapes.
+        app.register_console_var(
+            "turbine.model",
+            |world| {
+                let config = world.get_resource::<TurbineConfig>()?;
+                Some(config.model.clone().unwrap_or_else(|| "none".to_string()))
+            },
+            |world, value| {
+                world.resource_mut::<TurbineConfig>().model =
+                    (value != "none").then(|| value.to_string());
+                Ok(())
+            },
+        );
+        console_field!(app, "turbine.ring_lifetime", TurbineRingConfig, lifetime);
+        console_field!(app, "wind.strength", Wind, strength);
+        console_field!(app, "wind.varies", Wind, varies);
+        console_field!(app, "wind.mean_strength", Wind, mean_strength);
+    }
+}
+
+/// Reads the current value of a console variable, or `None` if it's unavailable.
+pub type ConsoleGetter = fn(&World) -> Option<String>;
+
+/// Parses and applies a new value to a console variable.
+pub type ConsoleSetter = fn(&mut World, &str) -> Result<(), String>;
+
+/// All variables reachable from the console, keyed by `<resource>.<field>` name.
+#[derive(Resource, Default)]
+pub struct ConsoleVars(BTreeMap<&'static str, (ConsoleGetter, ConsoleSetter)>);
+
+impl ConsoleVars {
+    /// Names of every registered variable, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// Reads a variable, or `None` if it's unknown or unavailable.
+    pub fn get(&self, world: &World, name: &str) -> Option<String> {
+        let (get, _) = self.0.get(name)?;
+        get(world)
+    }
+
+    /// Parses and applies a new value to a variable.
+    pub fn set(&self, world: &mut World, name: &str, value: &str) -> Result<(), String> {
+        let (_, set) = self
+            .0
+            .get(name)
+            .ok_or_else(|| format!("unknown variable `{name}`"))?;
+        set(world, value)
+    }
+}
+
+pub trait RegisterConsoleVar {
+    /// Makes a variable readable and writable from the in-game console.
+    fn register_console_var(
+        &mut self,
+        name: &'static str,
+        get: ConsoleGetter,
+        set: ConsoleSetter,
+    ) -> &mut Self;
+}
+
+impl RegisterConsoleVar for App {
+    fn register_console_var(
+        &mut self,
+        name: &'static str,
+        get: ConsoleGetter,
+        set: ConsoleSetter,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(ConsoleVars::default)
+            .0
+            .insert(name, (get, set));
+        self
+    }
+}
+
+/// Parses a console argument, producing a readable error on failure.
+pub fn parse_value<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}`"))
+}
+
+#[derive(Resource, Default)]
+struct ConsoleState {
+    open: bool,
+    input: String,
+    /// Submitted lines waiting to be executed.
+    pending: Vec<String>,
+    history: Vec<String>,
+}
+
+impl ConsoleState {
+    fn print(&mut self, line: String) {
+        self.history.push(line);
+        let overflow = self.history.len().saturating_sub(CONSOLE_HISTORY_LINES);
+        self.history.drain(..overflow);
+    }
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            ConsoleRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ConsoleText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn console_input(
+    mut state: ResMut<ConsoleState>,
+    mut events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+) {
+    let was_open = state.open;
+
+    for event in events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        if bindings.interface.console.contains(&event.key_code) {
+            state.open = !state.open;
+            continue;
+        }
+
+        if !state.open {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut state.input);
+                if !line.trim().is_empty() {
+                    state.pending.push(line);
+                }
+            }
+            Key::Backspace => {
+                state.input.pop();
+            }
+            Key::Escape => state.open = false,
+            Key::Space => state.input.push(' '),
+            Key::Character(text) => state.input.push_str(text),
+            _ => {}
+        }
+    }
+
+    // Swallow the keyboard while typing so the rest of the game doesn't react to it,
+    // including the key that closed the console this frame.
+    if was_open || state.open {
+        keys.reset_all();
+    }
+}
+
+fn run_console_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<ConsoleState>().pending);
+    if pending.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world, vars: Mut<ConsoleVars>| {
+        for line in pending {
+            let output = execute(world, &vars, &line);
+            let mut state = world.resource_mut::<ConsoleState>();
+            state.print(format!("> {line}"));
+            state.print(output);
+        }
+    });
+}
+
+fn execute(world: &mut World, vars: &ConsoleVars, line: &str) -> String {
+    let mut args = line.split_whitespace();
+
+    match (args.next(), args.next(), args.next()) {
+        (Some("list"), None, None) => vars.0.keys().copied().collect::<Vec<_>>().join(", "),
+        (Some("get"), Some(name), None) => match vars.0.get(name) {
+            Some((get, _)) => {
+                let value = get(world).unwrap_or_else(|| "unavailable".to_string());
+                format!("{name} = {value}")
+            }
+            None => format!("unknown variable `{name}`"),
+        },
+        (Some("set"), Some(name), Some(value)) => match vars.0.get(name) {
+            Some((get, set)) => match set(world, value) {
+                Ok(()) => {
+                    let value = get(world).unwrap_or_else(|| "unavailable".to_string());
+                    format!("{name} = {value}")
+                }
+                Err(error) => format!("error: {error}"),
+            },
+            None => format!("unknown variable `{name}`"),
+        },
+        _ => "usage: get <var> | set <var> <value> | list".to_string(),
+    }
+}
+
+fn update_console_text(
+    state: Res<ConsoleState>,
+    mut root: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut text: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    if let Ok(mut visibility) = root.get_single_mut() {
+        *visibility = if state.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    if let Ok(mut text) = text.get_single_mut() {
+        let mut contents = state.history.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        contents.push_str(&format!("> {}_", state.input));
+        text.sections[0].value = contents;
+    }
+}
@@ -0,0 +1,349 @@
+use avian3d::prelude::*;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy_tnua::prelude::*;
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+use crate::hud::{HudElement, HudRegion};
+use crate::player::{Health, Player, Stamina};
+
+/// A color scheme for the physics debug gizmos. `None` hides that kind of gizmo.
+struct PhysicsGizmoPreset {
+    name: &'static str,
+    aabb_color: Option<Color>,
+    collider_color: Option<Color>,
+    contact_point_color: Option<Color>,
+}
+
+/// Color schemes to pick from, so the gizmos stay visible against different terrain.
+const PHYSICS_GIZMO_PRESETS: [PhysicsGizmoPreset; 4] = [
+    PhysicsGizmoPreset {
+        name: "bright",
+        aabb_color: Some(Color::Srgba(css::WHITE)),
+        collider_color: Some(Color::Srgba(css::ORANGE)),
+        contact_point_color: Some(Color::Srgba(css::AQUA)),
+    },
+    PhysicsGizmoPreset {
+        name: "dark",
+        aabb_color: Some(Color::Srgba(css::BLACK)),
+        collider_color: Some(Color::Srgba(css::FUCHSIA)),
+        contact_point_color: Some(Color::Srgba(css::RED)),
+    },
+    PhysicsGizmoPreset {
+        name: "colliders only",
+        aabb_color: None,
+        collider_color: Some(Color::Srgba(css::YELLOW)),
+        contact_point_color: None,
+    },
+    PhysicsGizmoPreset {
+        name: "off",
+        aabb_color: None,
+        collider_color: None,
+        contact_point_color: None,
+    },
+];
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugConfig>()
+            .init_resource::<ContactGizmos>()
+            .init_resource::<SleepStats>()
+            .init_resource::<MovementStats>()
+            .add_systems(Startup, (spawn_sleep_stats_text, spawn_movement_stats_text))
+            .add_systems(
+                Update,
+                (
+                    toggle_contact_gizmos,
+                    cycle_physics_gizmo_colors.run_if(|config: Res<DebugConfig>| config.physics),
+                    draw_contact_gizmos.run_if(|config: Res<ContactGizmos>| config.enabled),
+                    wake_all_bodies,
+                    update_sleep_stats_text,
+                    update_movement_stats_text,
+                ),
+            );
+
+        console_field!(app, "bindings.debug.enabled", KeyBindings, debug.enabled);
+        console_field!(app, "contacts.enabled", ContactGizmos, enabled);
+        console_field!(app, "contacts.impulse_scale", ContactGizmos, impulse_scale);
+        console_field!(app, "physics.sleep_stats", SleepStats, enabled);
+        console_field!(app, "player.movement_stats", MovementStats, enabled);
+    }
+}
+
+/// Debug features chosen at startup.
+#[derive(Resource, Debug, Clone)]
+pub struct DebugConfig {
+    /// Add Avian's `PhysicsDebugPlugin`, which draws colliders, AABBs and contacts.
+    pub physics: bool,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            physics: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Reads `--debug-physics` and `--no-debug-physics` from the command line. Without
+    /// either, physics debug rendering is on in debug builds and off in release builds.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut config = Self::default();
+        for arg in args {
+            match arg.as_str() {
+                "--debug-physics" => config.physics = true,
+                "--no-debug-physics" => config.physics = false,
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+/// Draws each contact as an arrow along its normal, with the length growing with the
+/// normal impulse the solver applied.
+#[derive(Resource, Debug, Clone)]
+pub struct ContactGizmos {
+    pub enabled: bool,
+    /// Arrow length for a contact with no impulse.
+    pub base_length: f32,
+    /// Extra arrow length per unit of normal impulse.
+    pub impulse_scale: f32,
+    pub max_length: f32,
+    pub color: Color,
+}
+
+impl Default for ContactGizmos {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_length: 0.25,
+            impulse_scale: 5.0,
+            max_length: 3.0,
+            color: Color::from(css::ORANGE_RED),
+        }
+    }
+}
+
+/// Shows how many dynamic bodies are asleep in the HUD, to check that resting bodies
+/// actually go to sleep.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SleepStats {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+struct SleepStatsText;
+
+/// Shows the player's speed, whether they're on the ground, the gravity acting on them,
+/// and their health and stamina in the HUD.
+#[derive(Resource, Debug, Clone)]
+pub struct MovementStats {
+    pub enabled: bool,
+}
+
+impl Default for MovementStats {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Component)]
+struct MovementStatsText;
+
+fn toggle_contact_gizmos(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<ContactGizmos>,
+) {
+    if bindings
+        .debug
+        .just_pressed(&kb_input, &bindings.debug.contact_gizmos)
+    {
+        config.enabled = !config.enabled;
+    }
+}
+
+fn cycle_physics_gizmo_colors(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config_store: ResMut<GizmoConfigStore>,
+    mut preset_index: Local<usize>,
+) {
+    if !bindings
+        .debug
+        .just_pressed(&kb_input, &bindings.debug.physics_gizmo_preset)
+    {
+        return;
+    }
+
+    let preset = &PHYSICS_GIZMO_PRESETS[*preset_index];
+    *preset_index = (*preset_index + 1) % PHYSICS_GIZMO_PRESETS.len();
+
+    let (_, physics_gizmos) = config_store.config_mut::<PhysicsGizmos>();
+    physics_gizmos.aabb_color = preset.aabb_color;
+    physics_gizmos.collider_color = preset.collider_color;
+    physics_gizmos.contact_point_color = preset.contact_point_color;
+
+    info!("Physics gizmo colors: {}", preset.name);
+}
+
+fn draw_contact_gizmos(
+    mut gizmos: Gizmos,
+    config: Res<ContactGizmos>,
+    collisions: Res<Collisions>,
+    bodies: Query<(&Position, &Rotation)>,
+) {
+    for contacts in collisions.iter() {
+        // Contact data is stored relative to the first entity.
+        let Ok((position, rotation)) = bodies.get(contacts.entity1) else {
+            continue;
+        };
+
+        for manifold in contacts.manifolds.iter() {
+            for contact in manifold.contacts.iter() {
+                let point = contact.global_point1(position, rotation);
+                let normal = contact.global_normal1(rotation);
+                let length = (config.base_length + contact.normal_impulse * config.impulse_scale)
+                    .min(config.max_length);
+
+                gizmos.arrow(point, point + normal * length, config.color);
+            }
+        }
+    }
+}
+
+fn spawn_sleep_stats_text(mut commands: Commands) {
+    commands.spawn((
+        SleepStatsText,
+        HudElement::new(HudRegion::TopLeft),
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    ..default()
+                },
+            )
+        },
+    ));
+}
+
+fn update_sleep_stats_text(
+    config: Res<SleepStats>,
+    bodies: Query<(&RigidBody, Has<Sleeping>)>,
+    mut text: Query<(&mut Text, &mut Visibility), With<SleepStatsText>>,
+) {
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else {
+        return;
+    };
+
+    if !config.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let (mut asleep, mut awake) = (0, 0);
+    for (rigid_body, sleeping) in bodies.iter() {
+        if !rigid_body.is_dynamic() {
+            continue;
+        }
+        if sleeping {
+            asleep += 1;
+        } else {
+            awake += 1;
+        }
+    }
+
+    text.sections[0].value = format!("Dynamic bodies: {awake} awake, {asleep} asleep");
+}
+
+fn wake_all_bodies(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut sleeping: Query<(Entity, &mut TimeSleeping), With<Sleeping>>,
+) {
+    if !bindings
+        .debug
+        .just_pressed(&kb_input, &bindings.debug.wake_bodies)
+    {
+        return;
+    }
+
+    let mut count = 0;
+    for (entity, mut time_sleeping) in sleeping.iter_mut() {
+        commands.entity(entity).remove::<Sleeping>();
+        time_sleeping.0 = 0.0;
+        count += 1;
+    }
+
+    info!("Woke {count} sleeping bodies");
+}
+
+fn spawn_movement_stats_text(mut commands: Commands) {
+    commands.spawn((
+        MovementStatsText,
+        HudElement::new(HudRegion::TopLeft),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                ..default()
+            },
+        ),
+    ));
+}
+
+fn update_movement_stats_text(
+    config: Res<MovementStats>,
+    gravity: Res<Gravity>,
+    player: Query<(&LinearVelocity, &TnuaController, &Health, &Stamina), With<Player>>,
+    mut text: Query<(&mut Text, &mut Visibility), With<MovementStatsText>>,
+) {
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else {
+        return;
+    };
+
+    let Ok((velocity, controller, health, stamina)) = player.get_single() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    if !config.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let state = match controller.is_airborne() {
+        Ok(false) => "grounded",
+        Ok(true) => "airborne",
+        Err(_) => "no controller basis",
+    };
+    let g = gravity.0;
+    let exhausted = if stamina.exhausted {
+        " (exhausted)"
+    } else {
+        ""
+    };
+    text.sections[0].value = format!(
+        "Speed: {:.2} m/s, {state}\n\
+         Gravity: ({:.2}, {:.2}, {:.2})\n\
+         Health: {:.0}/{:.0}\n\
+         Stamina: {:.0}/{:.0}{exhausted}",
+        velocity.0.length(),
+        g.x,
+        g.y,
+        g.z,
+        health.current,
+        health.max,
+        stamina.current,
+        stamina.max,
+    );
+}
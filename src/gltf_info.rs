@@ -0,0 +1,250 @@
+use bevy::gltf::{Gltf, GltfMesh, GltfNode};
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+use bevy::render::primitives::Aabb;
+use bevy::utils::HashMap;
+
+pub struct GltfInfoPlugin;
+
+impl Plugin for GltfInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GltfRegistry>()
+            .add_systems(Update, inspect_gltf_assets);
+    }
+}
+
+/// Summary of a loaded glTF asset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfInfo {
+    pub scenes: usize,
+    pub meshes: usize,
+    pub primitives: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    /// Triangles across all triangle-list primitives.
+    pub triangles: usize,
+    /// Box around every primitive, in mesh space. Node transforms aren't applied, so
+    /// this is only exact for models whose meshes sit at the origin unscaled.
+    pub bounds: Option<Aabb>,
+    pub materials: usize,
+    /// Names of the named materials, sorted.
+    pub material_names: Vec<String>,
+    /// Vertex count of each primitive, mesh by mesh in file order.
+    pub primitive_vertices: Vec<usize>,
+    /// Every node as a path of names from its root, e.g. `"Tower/Nacelle/Blade1"`,
+    /// parents before their children.
+    pub nodes: Vec<String>,
+    /// Names of the named animation clips, sorted.
+    pub animations: Vec<String>,
+}
+
+/// Metadata for every loaded glTF asset, keyed by asset path (e.g. `"potato-1.glb"`).
+///
+/// Entries are refreshed when an asset is reloaded and evicted when it's removed.
+#[derive(Resource, Debug, Default)]
+pub struct GltfRegistry {
+    infos: HashMap<String, GltfInfo>,
+    paths: HashMap<AssetId<Gltf>, String>,
+}
+
+impl GltfRegistry {
+    /// Returns the info for the glTF loaded from `path`, if it's loaded.
+    pub fn get(&self, path: &str) -> Option<&GltfInfo> {
+        self.infos.get(path)
+    }
+
+    /// Returns the info for a glTF asset by id, if it's loaded.
+    pub fn get_by_id(&self, id: AssetId<Gltf>) -> Option<&GltfInfo> {
+        self.infos.get(self.paths.get(&id)?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GltfInfo)> {
+        self.infos.iter().map(|(path, info)| (path.as_str(), info))
+    }
+
+    fn insert(&mut self, id: AssetId<Gltf>, path: String, info: GltfInfo) {
+        // Reloading from a different path shouldn't leave the old entry behind.
+        if let Some(previous) = self.paths.insert(id, path.clone()) {
+            self.infos.remove(&previous);
+        }
+        self.infos.insert(path, info);
+    }
+
+    fn remove(&mut self, id: AssetId<Gltf>) {
+        if let Some(path) = self.paths.remove(&id) {
+            self.infos.remove(&path);
+        }
+    }
+}
+
+fn inspect_gltf_assets(
+    mut events: EventReader<AssetEvent<Gltf>>,
+    mut registry: ResMut<GltfRegistry>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for event in events.read() {
+        match *event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+                let (Some(gltf), Some(path)) = (gltfs.get(id), asset_server.get_path(id)) else {
+                    continue;
+                };
+                let info = gltf_info(gltf, &gltf_meshes, &gltf_nodes, &meshes);
+                registry.insert(id, path.path().to_string_lossy().into_owned(), info);
+            }
+            AssetEvent::Removed { id } => registry.remove(id),
+            _ => {}
+        }
+    }
+}
+
+fn gltf_info(
+    gltf: &Gltf,
+    gltf_meshes: &Assets<GltfMesh>,
+    gltf_nodes: &Assets<GltfNode>,
+    meshes: &Assets<Mesh>,
+) -> GltfInfo {
+    let mut info = GltfInfo {
+        scenes: gltf.scenes.len(),
+        meshes: gltf.meshes.len(),
+        materials: gltf.materials.len(),
+        material_names: gltf
+            .named_materials
+            .keys()
+            .map(|name| name.to_string())
+            .collect(),
+        ..default()
+    };
+    info.material_names.sort();
+
+    info.animations = gltf
+        .named_animations
+        .keys()
+        .map(|name| name.to_string())
+        .collect();
+    info.animations.sort();
+
+    // Roots are the nodes that aren't anyone's child.
+    let nodes: Vec<&GltfNode> = gltf
+        .nodes
+        .iter()
+        .filter_map(|handle| gltf_nodes.get(handle))
+        .collect();
+    let children: Vec<usize> = nodes
+        .iter()
+        .flat_map(|node| node.children.iter().map(|child| child.index))
+        .collect();
+    for root in nodes.iter().filter(|node| !children.contains(&node.index)) {
+        collect_node_paths(root, "", &mut info.nodes);
+    }
+
+    for gltf_mesh in gltf
+        .meshes
+        .iter()
+        .filter_map(|handle| gltf_meshes.get(handle))
+    {
+        info.primitives += gltf_mesh.primitives.len();
+
+        for mesh in gltf_mesh
+            .primitives
+            .iter()
+            .filter_map(|primitive| meshes.get(&primitive.mesh))
+        {
+            info.vertices += mesh.count_vertices();
+            info.primitive_vertices.push(mesh.count_vertices());
+
+            if mesh.primitive_topology() == PrimitiveTopology::TriangleList {
+                info.triangles += mesh
+                    .indices()
+                    .map_or(mesh.count_vertices(), |indices| indices.len())
+                    / 3;
+            }
+            if let Some(aabb) = mesh.compute_aabb() {
+                info.bounds = Some(match info.bounds {
+                    Some(bounds) => Aabb::from_min_max(
+                        bounds.min().min(aabb.min()).into(),
+                        bounds.max().max(aabb.max()).into(),
+                    ),
+                    None => aabb,
+                });
+            }
+            info.indices += mesh.indices().map_or(0, |indices| indices.len());
+        }
+    }
+
+    info
+}
+
+fn collect_node_paths(node: &GltfNode, parent: &str, paths: &mut Vec<String>) {
+    let path = if parent.is_empty() {
+        node.name.clone()
+    } else {
+        format!("{parent}/{}", node.name)
+    };
+    paths.push(path.clone());
+    for child in &node.children {
+        collect_node_paths(child, &path, paths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::gltf::GltfPlugin;
+    use bevy::scene::ScenePlugin;
+
+    use super::*;
+    use crate::turbine::TURBINE_MODEL;
+
+    #[test]
+    fn registers_the_turbine_model() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            ScenePlugin,
+            GltfPlugin::default(),
+            GltfInfoPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>();
+        app.finish();
+        app.cleanup();
+
+        let _model: Handle<Gltf> = app.world().resource::<AssetServer>().load(TURBINE_MODEL);
+        for _ in 0..500 {
+            app.update();
+            if app
+                .world()
+                .resource::<GltfRegistry>()
+                .get(TURBINE_MODEL)
+                .is_some()
+            {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let registry = app.world().resource::<GltfRegistry>();
+        let info = registry.get(TURBINE_MODEL).expect("the model never loaded");
+        assert_eq!(info.scenes, 1);
+        assert_eq!(info.meshes, 3);
+        assert_eq!(info.primitive_vertices, [24, 24, 24]);
+        assert_eq!(info.triangles, 36);
+        assert_eq!(info.material_names, ["Turbine"]);
+        assert_eq!(
+            info.nodes,
+            [
+                "Tower",
+                "Tower/Nacelle",
+                "Tower/Nacelle/Blade1",
+                "Tower/Nacelle/Blade2",
+                "Tower/Nacelle/Blade3",
+            ]
+        );
+    }
+}
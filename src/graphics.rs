@@ -0,0 +1,82 @@
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::camera::Exposure;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+
+/// Tonemappers worth comparing against the scene's lighting.
+const TONEMAPPERS: [Tonemapping; 8] = [
+    Tonemapping::TonyMcMapface,
+    Tonemapping::AcesFitted,
+    Tonemapping::Reinhard,
+    Tonemapping::ReinhardLuminance,
+    Tonemapping::AgX,
+    Tonemapping::BlenderFilmic,
+    Tonemapping::SomewhatBoringDisplayTransform,
+    Tonemapping::None,
+];
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenderConfig>()
+            .add_systems(Update, (cycle_tonemapping, apply_render_config).chain());
+
+        console_field!(app, "render.exposure", RenderConfig, exposure);
+    }
+}
+
+/// Camera exposure and tonemapping, applied to every 3D camera.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    /// Exposure in EV100. Higher values make the scene darker.
+    pub exposure: f32,
+    #[serde(skip)]
+    pub tonemapping: Tonemapping,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            exposure: Exposure::EV100_BLENDER,
+            tonemapping: Tonemapping::TonyMcMapface,
+        }
+    }
+}
+
+fn cycle_tonemapping(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<RenderConfig>,
+) {
+    if !bindings
+        .debug
+        .just_pressed(&kb_input, &bindings.debug.tonemapping)
+    {
+        return;
+    }
+
+    let current = TONEMAPPERS
+        .iter()
+        .position(|tonemapping| *tonemapping == config.tonemapping);
+    config.tonemapping = TONEMAPPERS[current.map_or(0, |i| (i + 1) % TONEMAPPERS.len())];
+    info!("Tonemapping: {:?}", config.tonemapping);
+}
+
+fn apply_render_config(
+    config: Res<RenderConfig>,
+    mut cameras: Query<(Ref<Camera3d>, &mut Tonemapping, &mut Exposure)>,
+) {
+    for (camera, mut tonemapping, mut exposure) in cameras.iter_mut() {
+        if !config.is_changed() && !camera.is_added() {
+            continue;
+        }
+
+        *tonemapping = config.tonemapping;
+        exposure.ev100 = config.exposure;
+    }
+}
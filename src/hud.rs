@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+
+/// Distance between the HUD and the window edges, and between stacked elements.
+const HUD_MARGIN: f32 = 12.0;
+
+const HUD_REGIONS: [HudRegion; 4] = [
+    HudRegion::TopLeft,
+    HudRegion::TopRight,
+    HudRegion::BottomLeft,
+    HudRegion::BottomRight,
+];
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudConfig>()
+            // The regions exist before `Startup`, so anything can register into them.
+            .add_systems(PreStartup, spawn_hud)
+            .add_systems(
+                Update,
+                (toggle_hud, apply_hud_visibility, attach_hud_elements).chain(),
+            );
+
+        console_field!(app, "hud.visible", HudConfig, visible);
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct HudConfig {
+    pub visible: bool,
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+/// A corner of the window that HUD elements stack into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HudRegion {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Places a UI node in the HUD. Elements in the same region are stacked in the order
+/// they were added, top to bottom, and are hidden along with the rest of the HUD.
+///
+/// Elements shouldn't position themselves; the region takes care of that.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HudElement {
+    pub region: HudRegion,
+}
+
+impl HudElement {
+    pub fn new(region: HudRegion) -> Self {
+        Self { region }
+    }
+}
+
+#[derive(Component)]
+struct HudRoot;
+
+#[derive(Component)]
+struct HudRegionNode(HudRegion);
+
+fn spawn_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            HudRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for region in HUD_REGIONS {
+                parent.spawn((HudRegionNode(region), region_node(region)));
+            }
+        });
+}
+
+fn region_node(region: HudRegion) -> NodeBundle {
+    let edge = Val::Px(HUD_MARGIN);
+    let (top, bottom) = match region {
+        HudRegion::TopLeft | HudRegion::TopRight => (edge, Val::Auto),
+        HudRegion::BottomLeft | HudRegion::BottomRight => (Val::Auto, edge),
+    };
+    let (left, right, align_items) = match region {
+        HudRegion::TopLeft | HudRegion::BottomLeft => (edge, Val::Auto, AlignItems::FlexStart),
+        HudRegion::TopRight | HudRegion::BottomRight => (Val::Auto, edge, AlignItems::FlexEnd),
+    };
+
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            top,
+            bottom,
+            left,
+            right,
+            flex_direction: FlexDirection::Column,
+            align_items,
+            row_gap: Val::Px(HUD_MARGIN / 2.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn toggle_hud(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<HudConfig>,
+) {
+    if kb_input.any_just_pressed(bindings.interface.hud.iter().copied()) {
+        config.visible = !config.visible;
+    }
+}
+
+fn apply_hud_visibility(config: Res<HudConfig>, mut root: Query<&mut Visibility, With<HudRoot>>) {
+    if !config.is_changed() {
+        return;
+    }
+
+    for mut visibility in root.iter_mut() {
+        *visibility = if config.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn attach_hud_elements(
+    mut commands: Commands,
+    elements: Query<(Entity, &HudElement), Changed<HudElement>>,
+    regions: Query<(Entity, &HudRegionNode)>,
+) {
+    for (element, hud_element) in elements.iter() {
+        let Some((region, _)) = regions
+            .iter()
+            .find(|(_, node)| node.0 == hud_element.region)
+        else {
+            warn!("No HUD region for {:?}", hud_element.region);
+            continue;
+        };
+
+        commands.entity(region).add_child(element);
+    }
+}
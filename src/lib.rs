@@ -0,0 +1,18 @@
+pub mod bindings;
+pub mod console;
+pub mod debug;
+pub mod gltf_info;
+pub mod graphics;
+pub mod hud;
+pub mod lighting;
+pub mod pause;
+pub mod photo;
+pub mod physics;
+pub mod player;
+pub mod potato;
+pub mod reset;
+pub mod settings;
+pub mod snapshot;
+pub mod terrain;
+pub mod trail;
+pub mod turbine;
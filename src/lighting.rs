@@ -0,0 +1,429 @@
+use std::f32::consts::PI;
+
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::view::RenderLayers;
+use bevy::transform::TransformSystem;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+use crate::hud::{HudElement, HudRegion};
+use crate::pause::GameState;
+use crate::player::{
+    PlayerCameraSet, WorldModelCamera, DEFAULT_RENDER_LAYER, VIEW_MODEL_RENDER_LAYER,
+};
+
+/// Sunlight color when the sun is on the horizon. It fades to white towards noon.
+const DAWN_COLOR: Color = Color::srgb(1.0, 0.55, 0.3);
+
+/// Sky color straight up at noon.
+const ZENITH_COLOR: Color = Color::srgb(0.25, 0.5, 0.95);
+
+/// Sky color at the horizon at noon.
+const HORIZON_COLOR: Color = Color::srgb(0.75, 0.85, 1.0);
+
+/// What's left of the sky's color at night.
+const NIGHT_SKY_TINT: Color = Color::srgb(0.02, 0.03, 0.08);
+
+/// Radius of the sky dome. It has to fit inside the camera's far plane.
+const SKY_RADIUS: f32 = 900.0;
+
+/// Turns the sun's path a little away from the world axes, so shadows don't line up
+/// with the terrain grid.
+const SUN_PATH_YAW: f32 = 0.5;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightingConfig>()
+            .init_resource::<DistanceFog>()
+            .init_resource::<DayNightCycle>()
+            .init_resource::<TimeOfDay>()
+            .add_systems(
+                Startup,
+                (
+                    spawn_sun,
+                    spawn_fill_light,
+                    spawn_sky,
+                    spawn_time_of_day_text,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        advance_time_of_day.run_if(not(in_state(GameState::Paused))),
+                        apply_time_of_day,
+                    )
+                        .chain(),
+                    (toggle_fog, apply_fog).chain(),
+                    update_time_of_day_text,
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                follow_camera_with_sky
+                    .after(TransformSystem::TransformPropagate)
+                    .after(PlayerCameraSet),
+            );
+
+        console_field!(
+            app,
+            "lighting.ambient_brightness",
+            LightingConfig,
+            ambient_brightness
+        );
+        console_field!(app, "lighting.fill_light", LightingConfig, fill_light);
+        console_field!(app, "lighting.fill_ratio", LightingConfig, fill_ratio);
+        console_field!(app, "lighting.sky", LightingConfig, sky);
+        console_field!(app, "fog.enabled", DistanceFog, enabled);
+        console_field!(app, "fog.start", DistanceFog, start);
+        console_field!(app, "fog.end", DistanceFog, end);
+        console_field!(app, "time.hour", TimeOfDay, hour);
+        console_field!(app, "time.paused", TimeOfDay, paused);
+        console_field!(app, "daynight.day_length", DayNightCycle, day_length);
+        console_field!(
+            app,
+            "daynight.noon_illuminance",
+            DayNightCycle,
+            noon_illuminance
+        );
+        console_field!(app, "daynight.night_ambient", DayNightCycle, night_ambient);
+    }
+}
+
+/// Light filling in the shadows, so the sides facing away from the sun aren't black.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingConfig {
+    pub ambient_color: Color,
+    /// Ambient brightness during the day. It fades to the night level as the sun sets.
+    pub ambient_brightness: f32,
+    /// Add a dimmer directional light shining from the opposite side to the sun.
+    pub fill_light: bool,
+    /// Brightness of the fill light, as a fraction of the sun's.
+    pub fill_ratio: f32,
+    /// Draw a gradient sky around the player instead of the plain clear color.
+    pub sky: bool,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            ambient_color: Color::WHITE,
+            ambient_brightness: 200.0,
+            fill_light: true,
+            fill_ratio: 0.25,
+            sky: true,
+        }
+    }
+}
+
+/// Fog on the first-person camera, fading the world out with distance.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DistanceFog {
+    pub enabled: bool,
+    pub color: Color,
+    /// Distance where the fog starts.
+    pub start: f32,
+    /// Distance where nothing can be seen through the fog.
+    pub end: f32,
+}
+
+impl Default for DistanceFog {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: Color::srgb(0.7, 0.75, 0.8),
+            start: 50.0,
+            end: 250.0,
+        }
+    }
+}
+
+/// How the sun moves and how bright the world gets over a day.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayNightCycle {
+    /// Length of a full day, in seconds.
+    pub day_length: f32,
+    /// Sunlight illuminance, in lux, with the sun at its highest.
+    pub noon_illuminance: f32,
+    /// Ambient brightness while the sun is down.
+    pub night_ambient: f32,
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        Self {
+            day_length: 600.0,
+            noon_illuminance: light_consts::lux::OVERCAST_DAY,
+            night_ambient: 5.0,
+        }
+    }
+}
+
+/// The current time of day, in hours from midnight. The sun rises at 6 and sets at 18.
+#[derive(Resource, Debug, Clone)]
+pub struct TimeOfDay {
+    pub hour: f32,
+    /// Stops the clock, leaving the sun where it is.
+    pub paused: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hour: 10.0,
+            paused: false,
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// How far the sun has travelled across the sky, from 0 at sunrise to PI at sunset.
+    /// Negative or above PI while the sun is down.
+    pub fn sun_angle(&self) -> f32 {
+        (self.hour - 6.0) / 12.0 * PI
+    }
+
+    /// How much daylight there is, from 0 while the sun is down to 1 at noon.
+    pub fn daylight(&self) -> f32 {
+        self.sun_angle().sin().max(0.0)
+    }
+}
+
+/// Marks the directional light following the time of day.
+#[derive(Debug, Component)]
+pub struct Sun;
+
+/// Marks the fill light, which stays opposite the sun.
+#[derive(Debug, Component)]
+pub struct FillLight;
+
+/// Marks the sky dome, which stays centered on the camera.
+#[derive(Debug, Component)]
+pub struct Sky;
+
+#[derive(Component)]
+struct TimeOfDayText;
+
+fn spawn_sun(mut commands: Commands) {
+    commands.spawn((
+        Sun,
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            },
+            ..default()
+        },
+        // The sun lights both the world model and the view model.
+        RenderLayers::from_layers(&[DEFAULT_RENDER_LAYER, VIEW_MODEL_RENDER_LAYER]),
+    ));
+}
+
+fn spawn_fill_light(mut commands: Commands) {
+    commands.spawn((
+        FillLight,
+        DirectionalLightBundle::default(),
+        RenderLayers::from_layers(&[DEFAULT_RENDER_LAYER, VIEW_MODEL_RENDER_LAYER]),
+    ));
+}
+
+fn spawn_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Fade from the horizon color to the zenith color going up. The material's color
+    // darkens the whole sky with the time of day.
+    let mut mesh = Sphere::new(SKY_RADIUS).mesh().uv(32, 18);
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|position| {
+            let height = (position[1] / SKY_RADIUS).clamp(0.0, 1.0);
+            HORIZON_COLOR
+                .mix(&ZENITH_COLOR, height.sqrt())
+                .to_linear()
+                .to_f32_array()
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    commands.spawn((
+        Sky,
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                // Seen from the inside.
+                cull_mode: None,
+                fog_enabled: false,
+                ..default()
+            }),
+            ..default()
+        },
+        NotShadowCaster,
+        NotShadowReceiver,
+    ));
+}
+
+/// Centers the sky on where the camera is rendered from this frame. That's only known
+/// after propagation, so the sky's `GlobalTransform` is set here as well.
+fn follow_camera_with_sky(
+    camera: Query<&GlobalTransform, (With<WorldModelCamera>, Without<Sky>)>,
+    mut sky: Query<(&mut Transform, &mut GlobalTransform), With<Sky>>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    for (mut transform, mut global_transform) in sky.iter_mut() {
+        transform.translation = camera.translation();
+        *global_transform = GlobalTransform::from(*transform);
+    }
+}
+
+fn advance_time_of_day(
+    time: Res<Time>,
+    cycle: Res<DayNightCycle>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    if time_of_day.paused || cycle.day_length <= 0.0 {
+        return;
+    }
+
+    let hours = time.delta_seconds() / cycle.day_length * 24.0;
+    time_of_day.hour = (time_of_day.hour + hours).rem_euclid(24.0);
+}
+
+fn apply_time_of_day(
+    config: Res<LightingConfig>,
+    cycle: Res<DayNightCycle>,
+    time_of_day: Res<TimeOfDay>,
+    mut ambient: ResMut<AmbientLight>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut sky: Query<(&Handle<StandardMaterial>, &mut Visibility), With<Sky>>,
+    mut sun: Query<(&mut Transform, &mut DirectionalLight), (With<Sun>, Without<FillLight>)>,
+    mut fill: Query<(&mut Transform, &mut DirectionalLight), (With<FillLight>, Without<Sun>)>,
+) {
+    if !config.is_changed() && !cycle.is_changed() && !time_of_day.is_changed() {
+        return;
+    }
+
+    let daylight = time_of_day.daylight();
+    let color = DAWN_COLOR.mix(&Color::WHITE, daylight.sqrt());
+    let illuminance = cycle.noon_illuminance * daylight;
+
+    for (mut transform, mut light) in sun.iter_mut() {
+        // The light shines along -Z, so tipping it down by the sun's angle points it
+        // at the horizon at sunrise and straight down at noon.
+        transform.rotation =
+            Quat::from_rotation_y(SUN_PATH_YAW) * Quat::from_rotation_x(-time_of_day.sun_angle());
+        light.illuminance = illuminance;
+        light.color = color;
+    }
+
+    for (mut transform, mut light) in fill.iter_mut() {
+        // Mirrored across the vertical, so it comes down from the other side of the sky.
+        transform.rotation = Quat::from_rotation_y(SUN_PATH_YAW)
+            * Quat::from_rotation_x(-(PI - time_of_day.sun_angle()));
+        light.illuminance = if config.fill_light {
+            illuminance * config.fill_ratio
+        } else {
+            0.0
+        };
+        light.color = color;
+    }
+
+    let sky_tint = NIGHT_SKY_TINT.mix(&color, daylight.sqrt());
+    for (material, mut visibility) in sky.iter_mut() {
+        *visibility = if config.sky {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = sky_tint;
+        }
+    }
+
+    ambient.color = config.ambient_color;
+    ambient.brightness = cycle
+        .night_ambient
+        .lerp(config.ambient_brightness, daylight);
+}
+
+fn spawn_time_of_day_text(mut commands: Commands) {
+    commands.spawn((
+        TimeOfDayText,
+        HudElement::new(HudRegion::TopRight),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                ..default()
+            },
+        ),
+    ));
+}
+
+fn update_time_of_day_text(
+    time_of_day: Res<TimeOfDay>,
+    mut text: Query<&mut Text, With<TimeOfDayText>>,
+) {
+    if !time_of_day.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+
+    let minutes = (time_of_day.hour * 60.0) as u32;
+    let paused = if time_of_day.paused { " (paused)" } else { "" };
+    text.sections[0].value = format!("Time: {:02}:{:02}{paused}", minutes / 60, minutes % 60);
+}
+
+fn toggle_fog(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut fog: ResMut<DistanceFog>,
+) {
+    if kb_input.any_just_pressed(bindings.interface.fog.iter().copied()) {
+        fog.enabled = !fog.enabled;
+    }
+}
+
+fn apply_fog(
+    mut commands: Commands,
+    fog: Res<DistanceFog>,
+    cameras: Query<(Entity, Ref<WorldModelCamera>)>,
+) {
+    for (camera, marker) in cameras.iter() {
+        if !fog.is_changed() && !marker.is_added() {
+            continue;
+        }
+
+        if fog.enabled {
+            commands.entity(camera).insert(FogSettings {
+                color: fog.color,
+                falloff: FogFalloff::Linear {
+                    start: fog.start,
+                    end: fog.end,
+                },
+                ..default()
+            });
+        } else {
+            commands.entity(camera).remove::<FogSettings>();
+        }
+    }
+}
@@ -3,52 +3,112 @@ use bevy::color::palettes::tailwind;
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
 
-pub mod player;
-pub mod potato;
-pub mod terrain;
-pub mod turbine;
-
-use player::*;
-use terrain::*;
-use turbine::*;
-use potato::PotatoPlugin;
+use miniature_potato::bindings::KeyBindings;
+use miniature_potato::console::ConsolePlugin;
+use miniature_potato::debug::{DebugConfig, DebugPlugin};
+use miniature_potato::gltf_info::GltfInfoPlugin;
+use miniature_potato::graphics::GraphicsPlugin;
+use miniature_potato::hud::*;
+use miniature_potato::lighting::LightingPlugin;
+use miniature_potato::pause::{GameState, PausePlugin};
+use miniature_potato::photo::PhotoPlugin;
+use miniature_potato::physics::*;
+use miniature_potato::player::*;
+use miniature_potato::potato::PotatoPlugin;
+use miniature_potato::reset::*;
+use miniature_potato::settings::SettingsPlugin;
+use miniature_potato::terrain::*;
+use miniature_potato::trail::TrailPlugin;
+use miniature_potato::turbine::*;
 
 fn main() {
-    App::new()
+    let debug_config = DebugConfig::from_args(std::env::args().skip(1));
+
+    let mut app = App::new();
+    app.insert_resource(debug_config.clone())
         .add_plugins((
             DefaultPlugins,
             PhysicsPlugins::default(),
-            // Enables debug rendering
-            PhysicsDebugPlugin::default(),
             PlayerPlugin,
             PotatoPlugin,
+            ConsolePlugin,
+            GltfInfoPlugin,
+            DebugPlugin,
+            ResetPlugin,
+            TrailPlugin,
+            GraphicsPlugin,
+            LightingPlugin,
+            PausePlugin,
+            SettingsPlugin,
+            HudPlugin,
+            PhotoPlugin,
         ))
-        // Overwrite default debug rendering configuration (optional)
-        .insert_gizmo_config(
-            PhysicsGizmos {
-                aabb_color: Some(Color::WHITE),
-                ..default()
-            },
-            GizmoConfig::default(),
-        )
+        .init_resource::<KeyBindings>()
+        .init_resource::<CcdConfig>()
+        .init_resource::<ColliderMarginConfig>()
+        .init_resource::<TerrainConfig>()
+        .init_resource::<TurbineConfig>()
+        .init_resource::<TurbineAssets>()
+        .init_resource::<TurbineRingConfig>()
+        .init_resource::<Wind>()
+        .init_resource::<TurbineEnergy>()
         .add_systems(
             Startup,
-            (spawn_lights, spawn_text, setup_wind_turbines, setup_terrain),
+            (
+                spawn_lights,
+                spawn_text,
+                setup_wind_turbines,
+                setup_terrain,
+                stream_terrain_chunks,
+                spawn_energy_text,
+            ),
         )
-        .add_systems(Update, (quit_on_esc_system, rotate_blades))
-        .run();
-}
+        .add_systems(
+            Update,
+            (
+                (vary_wind, tag_turbine_model_blades, rotate_blades)
+                    .chain()
+                    .run_if(not(in_state(GameState::Paused))),
+                (
+                    topple_unsupported_turbines.after(swap_terrain_collider),
+                    (spawn_turbine_ring, limit_turbine_count).chain(),
+                    remove_last_dropped_turbine,
+                    despawn_expired_turbines,
+                )
+                    .run_if(not(in_state(GameState::Paused))),
+                apply_collider_margins,
+                align_to_gravity,
+                (regenerate_terrain, stream_terrain_chunks).chain(),
+                swap_terrain_collider,
+                show_terrain_status,
+                update_instructions_text,
+                update_energy_text,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                despawn_all_turbines.in_set(ResetSet::Despawn),
+                reset_turbine_energy.in_set(ResetSet::Despawn),
+                setup_wind_turbines.in_set(ResetSet::Respawn),
+            )
+                .run_if(on_event::<ResetWorld>()),
+        );
 
-fn quit_on_esc_system(
-    _: Commands,
-    kb_input: Res<ButtonInput<KeyCode>>,
-    mut exit: EventWriter<AppExit>,
-) {
-    // Check if the Escape key is pressed
-    if kb_input.just_pressed(KeyCode::Escape) {
-        // Send the exit event to quit the game
-        exit.send(AppExit::Success);
+    // The plugin is left out entirely rather than hidden, so it costs nothing when off.
+    if debug_config.physics {
+        app.add_plugins(PhysicsDebugPlugin::default())
+            // Overwrite default debug rendering configuration (optional)
+            .insert_gizmo_config(
+                PhysicsGizmos {
+                    aabb_color: Some(Color::WHITE),
+                    ..default()
+                },
+                GizmoConfig::default(),
+            );
     }
+
+    app.run();
 }
 
 fn spawn_lights(mut commands: Commands) {
@@ -67,28 +127,33 @@ fn spawn_lights(mut commands: Commands) {
     ));
 }
 
-fn spawn_text(mut commands: Commands) {
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                bottom: Val::Px(12.0),
-                left: Val::Px(12.0),
+/// The control hints, generated from [`KeyBindings`].
+#[derive(Component)]
+struct InstructionsText;
+
+fn spawn_text(mut commands: Commands, bindings: Res<KeyBindings>) {
+    commands.spawn((
+        InstructionsText,
+        HudElement::new(HudRegion::BottomLeft),
+        TextBundle::from_section(
+            bindings.instructions(),
+            TextStyle {
+                font_size: 18.0,
                 ..default()
             },
-            ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                concat!(
-                    "Move the camera with your mouse.\n",
-                    "Use the scroll-wheel to change the FOV\n",
-                    "Use WASD to move. Use +/- to get taller/shorter."
-                ),
-                TextStyle {
-                    font_size: 25.0,
-                    ..default()
-                },
-            ));
-        });
+        ),
+    ));
+}
+
+fn update_instructions_text(
+    bindings: Res<KeyBindings>,
+    mut text: Query<&mut Text, With<InstructionsText>>,
+) {
+    if !bindings.is_changed() {
+        return;
+    }
+
+    for mut text in text.iter_mut() {
+        text.sections[0].value = bindings.instructions();
+    }
 }
@@ -0,0 +1,188 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::bindings::{key_name, KeyBindings};
+use crate::console_field;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .init_resource::<PauseConfig>()
+            .init_resource::<PhysicsPause>()
+            .add_systems(Update, (toggle_pause, pause_or_quit))
+            .add_systems(OnEnter(GameState::Paused), enter_pause)
+            .add_systems(OnExit(GameState::Paused), exit_pause);
+
+        console_field!(app, "pause.confirm_quit", PauseConfig, confirm_quit);
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct PauseConfig {
+    /// Make the quit key pause the game first, and only quit when pressed again while
+    /// paused. Turn it off to quit straight away, e.g. for headless runs.
+    pub confirm_quit: bool,
+}
+
+impl Default for PauseConfig {
+    fn default() -> Self {
+        Self { confirm_quit: true }
+    }
+}
+
+/// Whether the game is running. While paused, physics, the turbines, the wind and the
+/// time of day stand still, turbines can't be dropped or removed, and the player ignores
+/// movement and mouse look and takes no damage.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Playing,
+    Paused,
+}
+
+/// Why physics is paused. It only runs again once every reason is gone, so pausing the
+/// game and photo mode can be turned on and off in any order.
+#[derive(Resource, Debug, Default)]
+pub struct PhysicsPause {
+    reasons: HashSet<PhysicsPauseReason>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicsPauseReason {
+    Paused,
+    PhotoMode,
+}
+
+impl PhysicsPause {
+    pub fn add(&mut self, reason: PhysicsPauseReason, time: &mut Time<Physics>) {
+        self.reasons.insert(reason);
+        time.pause();
+    }
+
+    pub fn remove(&mut self, reason: PhysicsPauseReason, time: &mut Time<Physics>) {
+        self.reasons.remove(&reason);
+        if self.reasons.is_empty() {
+            time.unpause();
+        }
+    }
+}
+
+#[derive(Component)]
+struct PauseOverlay;
+
+fn toggle_pause(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !kb_input.any_just_pressed(bindings.interface.pause.iter().copied()) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        GameState::Playing => GameState::Paused,
+        GameState::Paused => GameState::Playing,
+    });
+}
+
+/// The quit key pauses the game, and quits when pressed again while paused.
+fn pause_or_quit(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<PauseConfig>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !kb_input.any_just_pressed(bindings.interface.quit.iter().copied()) {
+        return;
+    }
+
+    if config.confirm_quit && *state.get() == GameState::Playing {
+        next_state.set(GameState::Paused);
+    } else {
+        exit.send(AppExit::Success);
+    }
+}
+
+fn enter_pause(
+    mut commands: Commands,
+    bindings: Res<KeyBindings>,
+    config: Res<PauseConfig>,
+    mut physics_pause: ResMut<PhysicsPause>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    physics_pause.add(PhysicsPauseReason::Paused, &mut physics_time);
+
+    commands
+        .spawn((
+            PauseOverlay,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_sections([
+                    TextSection::new(
+                        "Paused\n",
+                        TextStyle {
+                            font_size: 48.0,
+                            ..default()
+                        },
+                    ),
+                    TextSection::new(
+                        pause_hints(&bindings, config.confirm_quit),
+                        TextStyle {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    ),
+                ])
+                .with_text_justify(JustifyText::Center),
+            );
+        });
+}
+
+fn exit_pause(
+    mut commands: Commands,
+    mut physics_pause: ResMut<PhysicsPause>,
+    mut physics_time: ResMut<Time<Physics>>,
+    overlay: Query<Entity, With<PauseOverlay>>,
+) {
+    physics_pause.remove(PhysicsPauseReason::Paused, &mut physics_time);
+
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// How to resume or quit, as shown on the pause overlay.
+fn pause_hints(bindings: &KeyBindings, confirm_quit: bool) -> String {
+    let keys = |keys: &[KeyCode]| {
+        let names: Vec<String> = keys.iter().map(|key| key_name(*key)).collect();
+        names.join("/")
+    };
+
+    let mut hints = Vec::new();
+    if !bindings.interface.pause.is_empty() {
+        hints.push(format!("{} to resume", keys(&bindings.interface.pause)));
+    }
+    if confirm_quit && !bindings.interface.quit.is_empty() {
+        hints.push(format!("{} again to quit", keys(&bindings.interface.quit)));
+    }
+    hints.join(", ")
+}
@@ -0,0 +1,117 @@
+use std::any::TypeId;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+use crate::hud::HudConfig;
+use crate::pause::{PhysicsPause, PhysicsPauseReason};
+
+pub struct PhotoPlugin;
+
+impl Plugin for PhotoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<PhotoMode>()
+            .init_resource::<PhotoModeConfig>()
+            .add_systems(Update, toggle_photo_mode)
+            .add_systems(OnEnter(PhotoMode::On), enter_photo_mode)
+            .add_systems(OnExit(PhotoMode::On), exit_photo_mode);
+
+        console_field!(app, "photo.pause_physics", PhotoModeConfig, pause_physics);
+    }
+}
+
+/// Photo mode hides the HUD and every gizmo for taking screenshots. Everything it
+/// changes is put back the way it was when it's turned off.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PhotoMode {
+    #[default]
+    Off,
+    On,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct PhotoModeConfig {
+    /// Freeze physics while in photo mode. The turbine blades keep turning.
+    pub pause_physics: bool,
+}
+
+impl Default for PhotoModeConfig {
+    fn default() -> Self {
+        Self {
+            pause_physics: true,
+        }
+    }
+}
+
+/// What photo mode changed, so it can be restored on exit.
+#[derive(Resource, Debug)]
+struct PhotoModeSnapshot {
+    hud_visible: bool,
+    gizmo_groups_enabled: Vec<(TypeId, bool)>,
+}
+
+fn toggle_photo_mode(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    state: Res<State<PhotoMode>>,
+    mut next_state: ResMut<NextState<PhotoMode>>,
+) {
+    if !kb_input.any_just_pressed(bindings.interface.photo_mode.iter().copied()) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        PhotoMode::Off => PhotoMode::On,
+        PhotoMode::On => PhotoMode::Off,
+    });
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    config: Res<PhotoModeConfig>,
+    mut hud: ResMut<HudConfig>,
+    mut gizmo_config_store: ResMut<GizmoConfigStore>,
+    mut physics_pause: ResMut<PhysicsPause>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    let mut gizmo_groups_enabled = Vec::new();
+    for (type_id, gizmo_config, _) in gizmo_config_store.iter_mut() {
+        gizmo_groups_enabled.push((*type_id, gizmo_config.enabled));
+        gizmo_config.enabled = false;
+    }
+
+    commands.insert_resource(PhotoModeSnapshot {
+        hud_visible: hud.visible,
+        gizmo_groups_enabled,
+    });
+
+    hud.visible = false;
+    if config.pause_physics {
+        physics_pause.add(PhysicsPauseReason::PhotoMode, &mut physics_time);
+    }
+}
+
+fn exit_photo_mode(
+    mut commands: Commands,
+    snapshot: Res<PhotoModeSnapshot>,
+    mut hud: ResMut<HudConfig>,
+    mut gizmo_config_store: ResMut<GizmoConfigStore>,
+    mut physics_pause: ResMut<PhysicsPause>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    hud.visible = snapshot.hud_visible;
+
+    for (type_id, enabled) in snapshot.gizmo_groups_enabled.iter() {
+        if let Some((gizmo_config, _)) = gizmo_config_store.get_config_mut_dyn(type_id) {
+            gizmo_config.enabled = *enabled;
+        }
+    }
+
+    // Removing a reason that was never added is fine, e.g. when `pause_physics` was
+    // changed while in photo mode.
+    physics_pause.remove(PhysicsPauseReason::PhotoMode, &mut physics_time);
+
+    commands.remove_resource::<PhotoModeSnapshot>();
+}
@@ -0,0 +1,192 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::player::Player;
+use crate::terrain::Terrain;
+
+/// Continuous collision detection settings.
+///
+/// Swept CCD stops fast-moving bodies from tunneling through thin colliders such as
+/// the terrain platform. It isn't free: every body with `SweptCcd` gets an extra
+/// shape cast along its motion each physics step, so it's only enabled where needed.
+#[derive(Resource, Debug, Clone)]
+pub struct CcdConfig {
+    /// Enable swept CCD on turbines once they topple. Standing turbines are static, so
+    /// they don't need it.
+    pub turbines: bool,
+    /// Enable swept CCD on the player. The player moves slowly enough that this is
+    /// normally unnecessary.
+    pub player: bool,
+}
+
+impl Default for CcdConfig {
+    fn default() -> Self {
+        Self {
+            turbines: true,
+            player: false,
+        }
+    }
+}
+
+/// Contact margins for a single collider.
+#[derive(Debug, Clone, Copy)]
+pub struct ColliderMargins {
+    /// Extra thickness treated as part of the collider's surface. Helps thin shapes
+    /// like the terrain trimesh resolve contacts without jitter.
+    pub collision: f32,
+    /// How far ahead of an actual hit contacts are generated, which keeps fast bodies
+    /// from sinking into facets before the solver reacts.
+    pub speculative: f32,
+}
+
+/// Collider margins for the player and terrain.
+///
+/// The terrain is faceted with hard edges, so the margins noticeably affect how
+/// smoothly the player walks over it. Changes are applied immediately.
+#[derive(Resource, Debug, Clone)]
+pub struct ColliderMarginConfig {
+    pub player: ColliderMargins,
+    pub terrain: ColliderMargins,
+}
+
+impl Default for ColliderMarginConfig {
+    fn default() -> Self {
+        Self {
+            player: ColliderMargins {
+                collision: 0.0,
+                speculative: 0.2,
+            },
+            terrain: ColliderMargins {
+                collision: 0.05,
+                speculative: 0.2,
+            },
+        }
+    }
+}
+
+pub fn apply_collider_margins(
+    mut commands: Commands,
+    config: Res<ColliderMarginConfig>,
+    bodies: Query<(Entity, Has<Player>, Ref<Collider>), Or<(With<Player>, With<Terrain>)>>,
+) {
+    for (entity, is_player, collider) in bodies.iter() {
+        if !config.is_changed() && !collider.is_added() {
+            continue;
+        }
+
+        let margins = if is_player {
+            config.player
+        } else {
+            config.terrain
+        };
+        commands.entity(entity).insert((
+            CollisionMargin(margins.collision),
+            SpeculativeMargin(margins.speculative),
+        ));
+    }
+}
+
+/// Summarizes what the collider builders see in a mesh, for diagnosing failures.
+pub fn describe_mesh(mesh: &Mesh) -> String {
+    let indices = match mesh.indices() {
+        Some(indices) => format!("{} indices", indices.len()),
+        None => "no indices".to_string(),
+    };
+
+    format!(
+        "a mesh with {} vertices, {indices} and {:?} topology",
+        mesh.count_vertices(),
+        mesh.primitive_topology()
+    )
+}
+
+/// The direction pointing away from gravity, or straight up when gravity is zero or
+/// not finite, e.g. after being set to nothing from the console. Anything turning
+/// gravity into a direction should go through this, so a bad value can't spread NaN
+/// into the transforms it's used for.
+pub fn gravity_up(gravity: &Gravity) -> Dir3 {
+    Dir3::new(-gravity.0).unwrap_or(Dir3::Y)
+}
+
+/// Turns a dynamic body so its local up axis points away from gravity.
+///
+/// A tumbling body is left alone until it has come to rest, then it's rolled upright.
+#[derive(Component, Debug, Clone)]
+pub struct GravityAligned {
+    /// Fastest the body turns while straightening up, in radians per second.
+    pub max_turn_speed: f32,
+    /// The body only straightens up once its linear speed drops below this.
+    pub rest_speed: f32,
+}
+
+impl Default for GravityAligned {
+    fn default() -> Self {
+        Self {
+            max_turn_speed: 2.0,
+            rest_speed: 0.5,
+        }
+    }
+}
+
+pub fn align_to_gravity(
+    gravity: Res<Gravity>,
+    mut bodies: Query<(
+        &GravityAligned,
+        &RigidBody,
+        &Rotation,
+        &LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+) {
+    let Ok(target_up) = Dir3::new(-gravity.0) else {
+        return;
+    };
+
+    for (aligned, rigid_body, rotation, linear_velocity, mut angular_velocity) in bodies.iter_mut()
+    {
+        if !rigid_body.is_dynamic() || linear_velocity.length() > aligned.rest_speed {
+            continue;
+        }
+
+        let up = rotation.0 * Vec3::Y;
+        // Upside down bodies have no preferred axis to roll over, so pick any.
+        let axis = up
+            .cross(*target_up)
+            .try_normalize()
+            .unwrap_or_else(|| up.any_orthonormal_vector());
+        let angle = up.angle_between(*target_up);
+        if angle < 0.01 {
+            continue;
+        }
+
+        // Keep any spin around the up axis and replace the rest with the correction,
+        // slowing down as the body nears upright so it doesn't overshoot.
+        let spin = up * angular_velocity.0.dot(up);
+        let correction = axis * (angle * 4.0).min(aligned.max_turn_speed);
+        angular_velocity.0 = spin + correction;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_up_points_away_from_gravity() {
+        let up = gravity_up(&Gravity(Vec3::new(0.0, 0.0, -9.81)));
+        assert!(up.abs_diff_eq(Vec3::Z, 1e-6));
+    }
+
+    #[test]
+    fn gravity_up_falls_back_to_straight_up() {
+        for gravity in [
+            Vec3::ZERO,
+            Vec3::splat(f32::NAN),
+            Vec3::new(0.0, f32::INFINITY, 0.0),
+        ] {
+            let up = gravity_up(&Gravity(gravity));
+            assert!(up.is_finite(), "{gravity} gave {up:?}");
+            assert_eq!(*up, Vec3::Y);
+        }
+    }
+}
@@ -1,17 +1,62 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
 use avian3d::prelude::*;
 use bevy::color::palettes::tailwind;
+use bevy::ecs::system::SystemParam;
 use bevy::input::mouse::*;
 use bevy::pbr::NotShadowCaster;
 use bevy::render::view::RenderLayers;
+use bevy::transform::TransformSystem;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
 use bevy::{color::palettes::css, prelude::*};
 use bevy_tnua::prelude::*;
 use bevy_tnua_avian3d::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+use crate::pause::GameState;
+use crate::physics::{gravity_up, CcdConfig};
+use crate::reset::{ResetSet, ResetWorld};
 
 #[derive(Debug, Component)]
 pub struct WorldModelCamera;
 
-/// Player movement speed factor.
-const PLAYER_SPEED: f32 = 10.;
+/// Default for [`PlayerRespawn::spawn_point`].
+pub const PLAYER_SPAWN: Vec3 = Vec3::new(0.0, 2.0, 0.0);
+
+/// Height the player's center floats above the ground. This must be greater (even if
+/// by little) than the distance between the center and the bottom of the capsule.
+const PLAYER_FLOAT_HEIGHT: f32 = 1.5;
+
+/// The player's capsule collider. Crouching swaps in a shorter one.
+const PLAYER_RADIUS: f32 = 0.5;
+const PLAYER_STANDING_LENGTH: f32 = 1.0;
+const PLAYER_CROUCHING_LENGTH: f32 = 0.2;
+
+/// How far above a candidate position the ground snap starts looking, so a player
+/// placed slightly inside the ground still finds the surface.
+const SNAP_RAYCAST_HEIGHT: f32 = 2.0;
+
+/// Longest distance the ground snap searches below the player.
+const SNAP_MAX_DISTANCE: f32 = 1000.0;
+
+/// Field of view of the world model camera at spawn, in degrees.
+const WORLD_MODEL_FOV: f32 = 90.0;
+
+/// Limits of the world model camera's field of view, in degrees.
+const MIN_FOV: f32 = 20.0;
+const MAX_FOV: f32 = 160.0;
+
+/// How far the grow and shrink keys can move the camera from the middle of the player,
+/// so it stays inside the body.
+const MIN_HEAD_OFFSET: f32 = -0.75;
+const MAX_HEAD_OFFSET: f32 = 0.75;
+
+/// How quickly the field of view follows the scroll-wheel zoom, as the fraction of the
+/// remaining change per second.
+const FOV_ZOOM_RESPONSE: f32 = 12.0;
 
 /// Used implicitly by all entities without a `RenderLayers` component.
 /// Our world model camera and all objects other than the player are on this layer.
@@ -32,26 +77,645 @@ impl Plugin for PlayerPlugin {
             TnuaControllerPlugin::default(),
             TnuaAvian3dPlugin::default(),
         ))
-        .add_systems(Startup, (player_setup,))
+        .add_systems(Startup, (player_setup, grab_cursor))
+        .add_systems(
+            Update,
+            (
+                player_look
+                    .run_if(not(in_state(GameState::Paused)))
+                    .run_if(primary_window_focused),
+                player_move.run_if(not(in_state(GameState::Paused))),
+                player_fov.run_if(primary_window_focused),
+                player_grow_shrink,
+            ),
+        )
+        .init_resource::<CameraShake>()
+        .add_systems(
+            Update,
+            ground_slam
+                .after(player_move)
+                .run_if(not(in_state(GameState::Paused))),
+        )
+        .init_resource::<PlayerInputConfig>()
+        .init_resource::<PlayerMovementConfig>()
+        .init_resource::<SpeedFov>()
+        .init_resource::<PlayerRespawn>()
+        .add_event::<RespawnPlayer>()
+        .init_resource::<FallDamage>()
+        .init_resource::<HeadBob>()
+        .add_systems(
+            Update,
+            apply_head_bob.after(player_look).after(player_grow_shrink),
+        )
+        .add_systems(
+            Update,
+            (respawn_lost_player, apply_fall_damage).run_if(not(in_state(GameState::Paused))),
+        )
+        .add_systems(
+            Update,
+            respawn_player
+                .in_set(ResetSet::Respawn)
+                .run_if(on_event::<RespawnPlayer>().or_else(on_event::<ResetWorld>())),
+        )
+        .add_systems(
+            Update,
+            (smooth_fov_zoom, apply_speed_fov).chain().after(player_fov),
+        )
+        .add_systems(
+            Update,
+            reset_player
+                .in_set(ResetSet::Respawn)
+                .run_if(on_event::<ResetWorld>()),
+        )
+        .add_systems(Update, toggle_cursor_grab.before(player_look))
+        .init_resource::<ThirdPersonCamera>()
         .add_systems(
             Update,
-            (player_look, player_move, player_fov, player_grow_shrink),
+            (toggle_third_person, apply_third_person)
+                .chain()
+                .after(player_grow_shrink),
+        )
+        .init_resource::<RollLock>()
+        .add_systems(
+            Update,
+            lock_player_roll
+                .after(player_look)
+                .run_if(|config: Res<RollLock>| config.enabled),
+        )
+        .init_resource::<CameraInterpolation>()
+        .add_systems(
+            PhysicsSchedule,
+            (snap_player_to_ground, record_physics_position)
+                .chain()
+                .in_set(PhysicsStepSet::Last),
+        )
+        .configure_sets(
+            PostUpdate,
+            PlayerCameraSet.after(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            PostUpdate,
+            (interpolate_player_cameras, apply_camera_shake)
+                .chain()
+                .in_set(PlayerCameraSet),
+        );
+
+        console_field!(app, "camera.interpolation", CameraInterpolation, enabled);
+        console_field!(app, "player.lock_roll", RollLock, enabled);
+        console_field!(app, "player.speed", PlayerInputConfig, movement_speed);
+        console_field!(
+            app,
+            "player.yaw_sensitivity",
+            PlayerInputConfig,
+            yaw_sensitivity
+        );
+        console_field!(
+            app,
+            "player.pitch_sensitivity",
+            PlayerInputConfig,
+            pitch_sensitivity
+        );
+        console_field!(app, "player.invert_x", PlayerInputConfig, invert_x);
+        console_field!(app, "player.invert_y", PlayerInputConfig, invert_y);
+        console_field!(app, "player.max_pitch", PlayerInputConfig, max_pitch);
+        console_field!(
+            app,
+            "player.sprint_multiplier",
+            PlayerMovementConfig,
+            sprint_multiplier
+        );
+        console_field!(app, "player.coyote_time", PlayerMovementConfig, coyote_time);
+        console_field!(
+            app,
+            "player.jump_buffer_time",
+            PlayerMovementConfig,
+            jump_buffer_time
+        );
+        console_field!(
+            app,
+            "player.min_jump_height",
+            PlayerMovementConfig,
+            min_jump_height
+        );
+        console_field!(
+            app,
+            "player.max_jump_height",
+            PlayerMovementConfig,
+            max_jump_height
+        );
+        console_field!(app, "player.air_jumps", PlayerMovementConfig, air_jumps);
+        console_field!(app, "player.slam_speed", PlayerMovementConfig, slam_speed);
+        console_field!(app, "camera.shake.enabled", CameraShake, enabled);
+        console_field!(
+            app,
+            "player.stamina_drain",
+            PlayerMovementConfig,
+            stamina_drain
         );
+        console_field!(
+            app,
+            "player.stamina_regen",
+            PlayerMovementConfig,
+            stamina_regen
+        );
+        console_field!(
+            app,
+            "player.respawn.max_distance",
+            PlayerRespawn,
+            max_distance
+        );
+        console_field!(
+            app,
+            "player.respawn.max_fall_time",
+            PlayerRespawn,
+            max_fall_time
+        );
+        console_field!(app, "player.respawn.min_height", PlayerRespawn, min_height);
+        console_field!(app, "fall_damage.enabled", FallDamage, enabled);
+        console_field!(app, "fall_damage.safe_speed", FallDamage, safe_speed);
+        console_field!(app, "fall_damage.scale", FallDamage, scale);
+        console_field!(app, "fall_damage.curve", FallDamage, curve);
+        console_field!(app, "head_bob.enabled", HeadBob, enabled);
+        console_field!(
+            app,
+            "head_bob.vertical_amplitude",
+            HeadBob,
+            vertical_amplitude
+        );
+        console_field!(
+            app,
+            "head_bob.lateral_amplitude",
+            HeadBob,
+            lateral_amplitude
+        );
+        console_field!(app, "head_bob.frequency", HeadBob, frequency);
+        console_field!(app, "camera.third_person", ThirdPersonCamera, enabled);
+        console_field!(
+            app,
+            "camera.third_person.distance",
+            ThirdPersonCamera,
+            distance
+        );
+        console_field!(app, "camera.third_person.height", ThirdPersonCamera, height);
+        console_field!(app, "fov.speed.enabled", SpeedFov, enabled);
+        console_field!(app, "fov.speed.max_boost", SpeedFov, max_boost);
+    }
+}
+
+/// Systems in `PostUpdate` that move the player's rendered cameras after transforms are
+/// propagated. Anything placed from a camera's `GlobalTransform` should run after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerCameraSet;
+
+/// Keeps the horizon level by removing any roll from the player relative to gravity,
+/// while preserving yaw and pitch. Disable it for modes that want free rotation.
+#[derive(Resource, Debug, Clone)]
+pub struct RollLock {
+    pub enabled: bool,
+}
+
+impl Default for RollLock {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// How fast the player moves and looks around.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerInputConfig {
+    /// Walking speed, and how fast the camera height changes.
+    pub movement_speed: f32,
+    /// Radians of turn per pixel of horizontal mouse movement.
+    pub yaw_sensitivity: f32,
+    /// Radians of turn per pixel of vertical mouse movement.
+    pub pitch_sensitivity: f32,
+    /// Turn right when the mouse moves left.
+    pub invert_x: bool,
+    /// Look down when the mouse moves up.
+    pub invert_y: bool,
+    /// How far the player can look up or down from the horizon, in degrees.
+    pub max_pitch: f32,
+    /// Gamepad stick deflection, from 0 to 1, below which the stick is ignored.
+    pub stick_dead_zone: f32,
+    /// Radians per second the view turns with the right stick fully deflected.
+    pub stick_look_speed: f32,
+}
+
+impl Default for PlayerInputConfig {
+    fn default() -> Self {
+        Self {
+            movement_speed: 10.0,
+            yaw_sensitivity: 0.003,
+            pitch_sensitivity: 0.002,
+            invert_x: false,
+            invert_y: false,
+            max_pitch: 80.0,
+            stick_dead_zone: 0.15,
+            stick_look_speed: 3.0,
+        }
+    }
+}
+
+/// How the player moves. The keys are in [`KeyBindings::movement`].
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerMovementConfig {
+    /// Walking speed is multiplied by this while sprinting.
+    pub sprint_multiplier: f32,
+    /// How quickly sprinting ramps up and back down, as the fraction of the full change
+    /// per second.
+    pub sprint_ramp: f32,
+    /// Walking speed is multiplied by this while crouching.
+    pub crouch_multiplier: f32,
+    /// Float height while crouching. Like the standing one, it must be greater than the
+    /// distance from the center of the crouching collider to its bottom.
+    pub crouch_float_height: f32,
+    /// Seconds after leaving the ground during which a jump still works, so running off
+    /// an edge doesn't need frame-perfect timing.
+    pub coyote_time: f32,
+    /// Seconds a jump held in the air is remembered, so pressing it just before landing
+    /// still jumps.
+    pub jump_buffer_time: f32,
+    /// Height of a jump when the key is tapped and released straight away.
+    pub min_jump_height: f32,
+    /// Height of a jump when the key is held until the peak.
+    pub max_jump_height: f32,
+    /// Extra jumps the player can make before landing again. 1 gives a double jump.
+    pub air_jumps: u32,
+    /// Speed of a ground slam.
+    pub slam_speed: f32,
+    /// Furthest above the ground a ground slam can be started from.
+    pub slam_max_height: f32,
+    /// [`CameraShake`] trauma added when a ground slam lands.
+    pub slam_trauma: f32,
+    /// [`Stamina`] used per second of full sprint.
+    pub stamina_drain: f32,
+    /// [`Stamina`] regained per second while not sprinting.
+    pub stamina_regen: f32,
+}
+
+impl Default for PlayerMovementConfig {
+    fn default() -> Self {
+        Self {
+            sprint_multiplier: 1.8,
+            sprint_ramp: 4.0,
+            crouch_multiplier: 0.4,
+            crouch_float_height: 0.9,
+            coyote_time: 0.12,
+            jump_buffer_time: 0.15,
+            min_jump_height: 1.0,
+            max_jump_height: 4.0,
+            air_jumps: 1,
+            slam_speed: 40.0,
+            slam_max_height: 100.0,
+            slam_trauma: 0.5,
+            stamina_drain: 25.0,
+            stamina_regen: 15.0,
+        }
+    }
+}
+
+/// Where the player spawns, and when they're considered lost and put back there.
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerRespawn {
+    /// Where the player is spawned, and returned to when respawning or resetting the
+    /// world. They're then snapped down onto the ground below it.
+    pub spawn_point: Vec3,
+    /// The player is respawned once they're further than this from the origin, or 0 to
+    /// let them go anywhere. Off by default, since the terrain streams in around the
+    /// player without an edge to fall off.
+    pub max_distance: f32,
+    /// The player is respawned after being in the air for this many seconds.
+    pub max_fall_time: f32,
+    /// The player is respawned once they're below this height, which is under any
+    /// terrain.
+    pub min_height: f32,
+}
+
+impl Default for PlayerRespawn {
+    fn default() -> Self {
+        Self {
+            spawn_point: PLAYER_SPAWN,
+            max_distance: 0.0,
+            max_fall_time: 8.0,
+            min_height: -50.0,
+        }
+    }
+}
+
+/// Puts the player back at [`PlayerRespawn::spawn_point`], at rest and with full
+/// [`Health`].
+#[derive(Event, Debug, Clone, Default)]
+pub struct RespawnPlayer;
+
+/// Hurts the player when they land hard. Landing at `safe_speed` or slower does
+/// nothing, so normal jumps are free. Above it the damage is
+/// `scale * (speed - safe_speed) ^ curve`.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallDamage {
+    pub enabled: bool,
+    /// Fastest landing, along gravity, that does no damage.
+    pub safe_speed: f32,
+    pub scale: f32,
+    /// 1 is linear, higher values make hard landings hurt disproportionately more.
+    pub curve: f32,
+}
+
+impl Default for FallDamage {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            safe_speed: 15.0,
+            scale: 5.0,
+            curve: 1.5,
+        }
+    }
+}
+
+/// The player respawns when this runs out.
+#[derive(Debug, Component, Clone)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn full(max: f32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Used up by sprinting. Once it runs out the player can only walk until it has
+/// refilled to [`Stamina::RECOVERED`].
+#[derive(Debug, Component, Clone)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    pub exhausted: bool,
+}
+
+impl Stamina {
+    /// Fraction of the maximum an exhausted player needs to get back to before
+    /// sprinting again.
+    pub const RECOVERED: f32 = 0.25;
+
+    pub fn full(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            exhausted: false,
+        }
+    }
+}
+
+/// Shakes the world model camera. `trauma` goes from 0 to 1 and wears off over time,
+/// and the shake grows with its square so small knocks stay subtle. Anything can add
+/// to it with [`CameraShake::add_trauma`].
+///
+/// The shake is only applied to the camera's `GlobalTransform`, after it has been
+/// rebuilt for the frame, so it never builds up.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraShake {
+    pub enabled: bool,
+    #[serde(skip)]
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// Largest offset of the camera along each axis, at full trauma.
+    pub max_offset: f32,
+    /// Largest turn of the camera around each axis, at full trauma, in degrees.
+    pub max_angle: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: 0.15,
+            max_angle: 3.0,
+        }
+    }
+}
+
+impl CameraShake {
+    /// Adds to the trauma, up to the maximum of 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
     }
 }
 
+/// The fastest the player has moved along gravity since leaving the ground.
+#[derive(Debug, Component, Default)]
+struct FallSpeed(f32);
+
+/// Bobs the world model camera up and down, and slightly side to side, while the player
+/// walks on the ground. The bob grows with speed and fades out when standing still or
+/// in the air.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeadBob {
+    pub enabled: bool,
+    /// Height of the bob at walking speed.
+    pub vertical_amplitude: f32,
+    /// Sideways sway at walking speed.
+    pub lateral_amplitude: f32,
+    /// Side to side sways per meter walked. The camera bobs up and down twice per sway,
+    /// once per step.
+    pub frequency: f32,
+    /// How quickly the bob fades in and out, per second.
+    pub response: f32,
+}
+
+impl Default for HeadBob {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vertical_amplitude: 0.05,
+            lateral_amplitude: 0.025,
+            frequency: 0.35,
+            response: 8.0,
+        }
+    }
+}
+
+/// The world model camera's place in the [`HeadBob`] cycle, and the offset it's
+/// currently moved by.
+#[derive(Debug, Component, Default)]
+struct HeadBobState {
+    /// Radians through the sway.
+    phase: f32,
+    /// How much of the full bob is applied, relative to walking speed.
+    intensity: f32,
+    /// Offset added to the camera's translation, in the player's local space.
+    offset: Vec3,
+}
+
+/// Moves the world model camera behind and above the player, looking at them, and
+/// shows the player's body instead of the arm. It still turns and pitches with the
+/// player. The key is in [`KeyBindings::interface`].
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThirdPersonCamera {
+    #[serde(skip)]
+    pub enabled: bool,
+    /// How far behind the player the camera sits.
+    pub distance: f32,
+    /// How far above the player the camera sits.
+    pub height: f32,
+}
+
+impl Default for ThirdPersonCamera {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance: 6.0,
+            height: 1.5,
+        }
+    }
+}
+
+/// How far the world model camera is currently moved back for [`ThirdPersonCamera`],
+/// in the player's local space.
+#[derive(Debug, Component, Default)]
+struct ThirdPersonOffset(Vec3);
+
+/// How far the grow and shrink keys have moved the world model camera up, within
+/// [`MIN_HEAD_OFFSET`] and [`MAX_HEAD_OFFSET`]. It's kept when the player respawns.
+#[derive(Debug, Component, Default)]
+struct HeadOffset(f32);
+
+/// The first-person arm, hidden while in third person.
 #[derive(Debug, Component)]
-struct Player;
+struct ViewModelArm;
+
+/// Widens the field of view as the player moves faster, on top of the scroll-wheel zoom.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedFov {
+    pub enabled: bool,
+    /// Largest widening, in degrees.
+    pub max_boost: f32,
+    /// Horizontal speed at which the full boost is reached.
+    pub full_boost_speed: f32,
+    /// Shapes the speed to boost curve. 1 is linear, higher values keep the boost small
+    /// until the player is close to full speed.
+    pub curve: f32,
+    /// How quickly the boost follows changes in speed, per second.
+    pub response: f32,
+}
+
+impl Default for SpeedFov {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_boost: 10.0,
+            full_boost_speed: PlayerInputConfig::default().movement_speed,
+            curve: 2.0,
+            response: 4.0,
+        }
+    }
+}
+
+/// The part of the world model camera's field of view currently added by [`SpeedFov`],
+/// in radians.
+#[derive(Debug, Component, Default)]
+struct FovBoost(f32);
+
+/// The field of view the scroll-wheel zoom is easing the world model camera towards,
+/// in radians, not counting any [`FovBoost`].
+#[derive(Debug, Component)]
+struct TargetFov(f32);
+
+impl Default for TargetFov {
+    fn default() -> Self {
+        Self(WORLD_MODEL_FOV.to_radians())
+    }
+}
+
+/// Smooths the player's cameras between physics steps.
+///
+/// Physics runs on a fixed timestep, so at higher frame rates the player only moves
+/// on some frames and the view stutters. With this enabled the cameras are rendered
+/// at a position interpolated between the last two physics steps.
+#[derive(Resource, Debug, Clone)]
+pub struct CameraInterpolation {
+    pub enabled: bool,
+}
+
+impl Default for CameraInterpolation {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Player positions after the last two physics steps.
+#[derive(Debug, Component)]
+struct PhysicsPositionHistory {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl PhysicsPositionHistory {
+    fn at(position: Vec3) -> Self {
+        Self {
+            previous: position,
+            current: position,
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Player;
+
+/// How many jumps the player has made in the air since last standing on the ground.
+#[derive(Debug, Component, Default)]
+struct AirJumps(u32);
+
+/// Places the player on the ground below it after the next physics step, then removes
+/// itself. Insert it whenever the player is moved.
+#[derive(Debug, Component)]
+pub struct SnapToGround;
+
+/// Finds where the player should be placed so it floats just above the surface below
+/// `position`, along gravity. Returns `None` when there's no surface.
+pub fn snap_to_ground(
+    position: Vec3,
+    spatial_query: &SpatialQuery,
+    gravity: &Gravity,
+    player: Entity,
+) -> Option<Vec3> {
+    let down = Dir3::new(gravity.0).unwrap_or(Dir3::NEG_Y);
+    let origin = position - *down * SNAP_RAYCAST_HEIGHT;
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player]);
+
+    let hit = spatial_query.cast_ray(
+        origin,
+        down,
+        SNAP_RAYCAST_HEIGHT + SNAP_MAX_DISTANCE,
+        true,
+        filter,
+    )?;
+
+    let ground = origin + *down * hit.time_of_impact;
+    Some(ground - *down * PLAYER_FLOAT_HEIGHT)
+}
 
 fn player_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    ccd: Res<CcdConfig>,
+    respawn: Res<PlayerRespawn>,
 ) {
     let arm = meshes.add(Cuboid::new(0.1, 0.1, 0.5));
     let arm_material = materials.add(Color::from(tailwind::TEAL_200));
 
-    commands
+    let player = commands
         .spawn((
             Player,
             PbrBundle {
@@ -60,13 +724,13 @@ fn player_setup(
                     half_length: 0.5,
                 }),
                 material: materials.add(Color::from(css::DARK_CYAN)),
-                transform: Transform::from_xyz(0.0, 2.0, 0.0),
+                transform: Transform::from_translation(respawn.spawn_point),
                 ..Default::default()
             },
             // The player character needs to be configured as a dynamic rigid body of the physics
             // engine.
             RigidBody::Dynamic,
-            Collider::capsule(0.5, 1.0),
+            Collider::capsule(PLAYER_RADIUS, PLAYER_STANDING_LENGTH),
             // This bundle holds the main components.
             TnuaControllerBundle::default(),
             // A sensor shape is not strictly necessary, but without it we'll get weird results.
@@ -75,13 +739,24 @@ fn player_setup(
             // By locking the rotation we can prevent this.
             LockedAxes::ROTATION_LOCKED,
             RenderLayers::layer(VIEW_MODEL_RENDER_LAYER),
+            PhysicsPositionHistory::at(respawn.spawn_point),
+            SnapToGround,
+            AirJumps::default(),
+            Health::full(100.0),
+            Stamina::full(100.0),
+            FallSpeed::default(),
         ))
         .with_children(|parent| {
             parent.spawn((
                 WorldModelCamera,
+                FovBoost::default(),
+                TargetFov::default(),
+                HeadBobState::default(),
+                ThirdPersonOffset::default(),
+                HeadOffset::default(),
                 Camera3dBundle {
                     projection: PerspectiveProjection {
-                        fov: 90.0_f32.to_radians(),
+                        fov: WORLD_MODEL_FOV.to_radians(),
                         ..default()
                     }
                     .into(),
@@ -110,6 +785,7 @@ fn player_setup(
 
             // Spawn the player's right arm.
             parent.spawn((
+                ViewModelArm,
                 MaterialMeshBundle {
                     mesh: arm,
                     material: arm_material,
@@ -121,32 +797,360 @@ fn player_setup(
                 // The arm is free-floating, so shadows would look weird.
                 NotShadowCaster,
             ));
-        });
+        })
+        .id();
+
+    if ccd.player {
+        commands.entity(player).insert(SweptCcd::default());
+    }
+}
+
+/// Sends [`RespawnPlayer`] when the player has wandered too far or has been falling
+/// for too long, which usually means they went off the edge of the world.
+fn respawn_lost_player(
+    time: Res<Time>,
+    config: Res<PlayerRespawn>,
+    player: Query<(&Transform, &TnuaController), With<Player>>,
+    mut respawn: EventWriter<RespawnPlayer>,
+    mut fall_time: Local<f32>,
+) {
+    let Ok((transform, controller)) = player.get_single() else {
+        return;
+    };
+
+    if controller.is_airborne().unwrap_or(false) {
+        *fall_time += time.delta_seconds();
+    } else {
+        *fall_time = 0.0;
+    }
+
+    let too_far = config.max_distance > 0.0 && transform.translation.length() > config.max_distance;
+    if too_far || transform.translation.y < config.min_height || *fall_time > config.max_fall_time {
+        *fall_time = 0.0;
+        respawn.send(RespawnPlayer);
+    }
+}
+
+fn respawn_player(
+    mut commands: Commands,
+    config: Res<PlayerRespawn>,
+    mut player: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+            &mut PhysicsPositionHistory,
+            &mut Health,
+            &mut Stamina,
+            &mut FallSpeed,
+        ),
+        With<Player>,
+    >,
+) {
+    let Ok((
+        entity,
+        mut transform,
+        mut linear_velocity,
+        mut angular_velocity,
+        mut history,
+        mut health,
+        mut stamina,
+        mut fall_speed,
+    )) = player.get_single_mut()
+    else {
+        return;
+    };
+
+    *transform = Transform::from_translation(config.spawn_point);
+    commands.entity(entity).insert(SnapToGround);
+    linear_velocity.0 = Vec3::ZERO;
+    angular_velocity.0 = Vec3::ZERO;
+    // Don't interpolate across the teleport.
+    *history = PhysicsPositionHistory::at(config.spawn_point);
+    health.current = health.max;
+    *stamina = Stamina::full(stamina.max);
+    fall_speed.0 = 0.0;
+}
+
+/// Tracks how fast the player falls, and takes health away when they land. Running out of
+/// health respawns them.
+fn apply_fall_damage(
+    config: Res<FallDamage>,
+    gravity: Res<Gravity>,
+    mut player: Query<
+        (
+            &TnuaController,
+            &LinearVelocity,
+            &mut Health,
+            &mut FallSpeed,
+        ),
+        With<Player>,
+    >,
+    mut respawn: EventWriter<RespawnPlayer>,
+    mut shake: ResMut<CameraShake>,
+) {
+    let Ok((controller, velocity, mut health, mut fall_speed)) = player.get_single_mut() else {
+        return;
+    };
+
+    // By the time the controller reports the landing, the ground has already stopped
+    // the player, so remember the speed from on the way down.
+    if controller.is_airborne().unwrap_or(false) {
+        let down = gravity.0.normalize_or_zero();
+        fall_speed.0 = fall_speed.0.max(velocity.0.dot(down));
+        return;
+    }
+
+    let impact_speed = std::mem::take(&mut fall_speed.0);
+    if !config.enabled || impact_speed <= config.safe_speed {
+        return;
+    }
+
+    let damage = config.scale * (impact_speed - config.safe_speed).powf(config.curve);
+    health.current = (health.current - damage).max(0.0);
+    if health.max > 0.0 {
+        shake.add_trauma(damage / health.max);
+    }
+    info!("Landed at {impact_speed:.1} m/s and took {damage:.0} damage");
+
+    if health.current <= 0.0 {
+        respawn.send(RespawnPlayer);
+    }
+}
+
+/// The player itself is moved back by [`respawn_player`].
+fn reset_player(
+    mut camera: Query<
+        (
+            &mut Transform,
+            &mut Projection,
+            &mut FovBoost,
+            &mut TargetFov,
+            &mut HeadBobState,
+            &mut ThirdPersonOffset,
+            &mut HeadOffset,
+        ),
+        With<WorldModelCamera>,
+    >,
+) {
+    // Undo any height and FOV changes. The third-person offset is put back by
+    // `apply_third_person` if it's still enabled.
+    if let Ok((
+        mut transform,
+        mut projection,
+        mut boost,
+        mut target,
+        mut head_bob,
+        mut third_person,
+        mut head_offset,
+    )) = camera.get_single_mut()
+    {
+        *transform = Transform::default();
+        *head_bob = HeadBobState::default();
+        *third_person = ThirdPersonOffset::default();
+        *head_offset = HeadOffset::default();
+        boost.0 = 0.0;
+        *target = TargetFov::default();
+        if let Projection::Perspective(ref mut perspective) = projection.as_mut() {
+            perspective.fov = WORLD_MODEL_FOV.to_radians();
+        }
+    }
+}
+
+/// Runs at the end of the physics step, once the spatial query includes any colliders
+/// that were spawned along with the player.
+fn snap_player_to_ground(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    gravity: Res<Gravity>,
+    mut player: Query<
+        (
+            Entity,
+            &mut Position,
+            &mut LinearVelocity,
+            &mut PhysicsPositionHistory,
+        ),
+        With<SnapToGround>,
+    >,
+) {
+    for (entity, mut position, mut linear_velocity, mut history) in player.iter_mut() {
+        commands.entity(entity).remove::<SnapToGround>();
+
+        let Some(snapped) = snap_to_ground(position.0, &spatial_query, &gravity, entity) else {
+            warn!(
+                "No ground below the player at {}, leaving it there",
+                position.0
+            );
+            continue;
+        };
+
+        position.0 = snapped;
+        linear_velocity.0 = Vec3::ZERO;
+        // Don't interpolate across the snap.
+        *history = PhysicsPositionHistory::at(snapped);
+    }
+}
+
+fn record_physics_position(mut player: Query<(&Position, &mut PhysicsPositionHistory)>) {
+    for (position, mut history) in player.iter_mut() {
+        history.previous = history.current;
+        history.current = position.0;
+    }
+}
+
+/// Offsets the rendered cameras from the latest physics position to one interpolated
+/// between the last two steps. The cameras' `GlobalTransform` is rebuilt from the
+/// player's and their own `Transform`, so the offset never builds up, and physics and
+/// input never see the interpolated position.
+///
+/// The rebuild happens even with the interpolation disabled, so [`apply_camera_shake`]
+/// always starts from a clean transform, including on frames where propagation leaves
+/// the cameras alone because nothing moved.
+fn interpolate_player_cameras(
+    config: Res<CameraInterpolation>,
+    time: Res<Time<Fixed>>,
+    player: Query<(&PhysicsPositionHistory, &GlobalTransform, &Children), With<Player>>,
+    mut cameras: Query<(&Transform, &mut GlobalTransform), (With<Camera>, Without<Player>)>,
+) {
+    let Ok((history, player_transform, children)) = player.get_single() else {
+        return;
+    };
+
+    // Physics steps on the fixed timestep, so this is how far the clock has run past
+    // the last step, as a fraction of a step.
+    let alpha = if config.enabled {
+        time.overstep_fraction().clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let offset = history.previous.lerp(history.current, alpha) - history.current;
+
+    for &child in children.iter() {
+        let Ok((transform, mut global_transform)) = cameras.get_mut(child) else {
+            continue;
+        };
+        let mut rebuilt = player_transform
+            .mul_transform(*transform)
+            .compute_transform();
+        rebuilt.translation += offset;
+        *global_transform = GlobalTransform::from(rebuilt);
+    }
+}
+
+/// Sticks and buttons of whichever gamepads are connected.
+#[derive(SystemParam)]
+struct GamepadInput<'w> {
+    gamepads: Res<'w, Gamepads>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
 }
 
+impl GamepadInput<'_> {
+    /// The most deflected stick across all gamepads, with the dead zone removed and the
+    /// rest rescaled so the output still starts at zero.
+    fn stick(&self, x: GamepadAxisType, y: GamepadAxisType, dead_zone: f32) -> Vec2 {
+        let stick = self
+            .gamepads
+            .iter()
+            .map(|gamepad| {
+                Vec2::new(
+                    self.axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+                    self.axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+                )
+            })
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or(Vec2::ZERO);
+
+        let length = stick.length();
+        if length <= dead_zone || dead_zone >= 1.0 {
+            return Vec2::ZERO;
+        }
+        stick / length * ((length - dead_zone) / (1.0 - dead_zone)).min(1.0)
+    }
+
+    fn left_stick(&self, dead_zone: f32) -> Vec2 {
+        self.stick(
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+            dead_zone,
+        )
+    }
+
+    fn right_stick(&self, dead_zone: f32) -> Vec2 {
+        self.stick(
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+            dead_zone,
+        )
+    }
+
+    fn pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads
+            .iter()
+            .any(|gamepad| self.buttons.pressed(GamepadButton::new(gamepad, button)))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn player_move(
+    time: Res<Time>,
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut controller: Query<(&mut TnuaController, &Transform), With<Player>>,
+    gamepad: GamepadInput,
+    bindings: Res<KeyBindings>,
+    input_config: Res<PlayerInputConfig>,
+    config: Res<PlayerMovementConfig>,
+    gravity: Res<Gravity>,
+    spatial_query: SpatialQuery,
+    mut controller: Query<
+        (
+            Entity,
+            &mut TnuaController,
+            &mut Collider,
+            &Transform,
+            &mut AirJumps,
+            &mut Stamina,
+        ),
+        With<Player>,
+    >,
+    // How far into sprinting the player is, from 0 (walking) to 1 (full sprint).
+    mut sprint: Local<f32>,
+    mut crouched: Local<bool>,
 ) {
-    let Ok((mut controller, transform)) = controller.get_single_mut() else {
+    let Ok((player, mut controller, mut collider, transform, mut air_jumps, mut stamina)) =
+        controller.get_single_mut()
+    else {
         return;
     };
 
+    // This reads the basis fed on the previous frame. The walk basis only counts as
+    // airborne once its coyote time has run out, so until then a jump works as if the
+    // player were still on the ground.
+    let airborne = controller.is_airborne().unwrap_or(false);
+    if !airborne {
+        air_jumps.0 = 0;
+    }
+
+    let keys = &bindings.movement;
     let mut direction = Vec3::ZERO;
 
-    if keyboard.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+    if keyboard.any_pressed(keys.forward.iter().copied()) {
         direction -= Vec3::Z;
     }
-    if keyboard.any_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+    if keyboard.any_pressed(keys.back.iter().copied()) {
         direction += Vec3::Z;
     }
-    if keyboard.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+    if keyboard.any_pressed(keys.left.iter().copied()) {
         direction -= Vec3::X;
     }
-    if keyboard.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+    if keyboard.any_pressed(keys.right.iter().copied()) {
         direction += Vec3::X;
     }
 
+    // The left stick adds to the keys, so both work at once.
+    let stick = gamepad.left_stick(input_config.stick_dead_zone);
+    direction += Vec3::new(stick.x, 0.0, -stick.y);
+
     // NOTE: Restricting the player movement to XZ plane might be incorrect
     // after there are slopes the player must navigate up and down.
 
@@ -161,33 +1165,192 @@ fn player_move(
     // Calculate the movement in the XZ plane
     let direction = forward_xz * direction.z + right_xz * direction.x;
 
+    // Crouch while the key is held, and stay crouched after it's released until there's
+    // room to stand up.
+    if keyboard.any_pressed(keys.crouch.iter().copied()) {
+        if !*crouched {
+            *crouched = true;
+            *collider = Collider::capsule(PLAYER_RADIUS, PLAYER_CROUCHING_LENGTH);
+        }
+    } else if *crouched && has_headroom(&spatial_query, &gravity, player, transform, &config) {
+        *crouched = false;
+        *collider = Collider::capsule(PLAYER_RADIUS, PLAYER_STANDING_LENGTH);
+    }
+
+    // Only sprint while actually moving and not out of breath, and ease in and out of it.
+    let sprint_pressed = keyboard.any_pressed(keys.sprint.iter().copied())
+        || gamepad.pressed(GamepadButtonType::RightTrigger2);
+    let sprinting = direction != Vec3::ZERO && !*crouched && sprint_pressed && !stamina.exhausted;
+    let sprint_target = if sprinting { 1.0 } else { 0.0 };
+    let max_step = config.sprint_ramp * time.delta_seconds();
+    *sprint += (sprint_target - *sprint).clamp(-max_step, max_step);
+
+    if sprinting {
+        stamina.current -= config.stamina_drain * *sprint * time.delta_seconds();
+    } else {
+        stamina.current += config.stamina_regen * time.delta_seconds();
+    }
+    stamina.current = stamina.current.clamp(0.0, stamina.max);
+    if stamina.current <= 0.0 {
+        stamina.exhausted = true;
+    } else if stamina.current >= stamina.max * Stamina::RECOVERED {
+        stamina.exhausted = false;
+    }
+    let mut speed =
+        input_config.movement_speed * (1.0 + (config.sprint_multiplier - 1.0) * *sprint);
+    if *crouched {
+        speed *= config.crouch_multiplier;
+    }
+
     // Feed the basis every frame. Even if the player doesn't move - just use `desired_velocity:
     // Vec3::ZERO`. `TnuaController` starts without a basis, which will make the character collider
     // just fall.
     controller.basis(TnuaBuiltinWalk {
         // The `desired_velocity` determines how the character will move.
-        desired_velocity: direction.normalize_or_zero() * 10.0,
+        // Clamped rather than normalized, so a half-deflected stick walks at half speed.
+        desired_velocity: direction.clamp_length_max(1.0) * speed,
         // The `float_height` must be greater (even if by little) from the distance between the
         // character's center and the lowest point of its collider.
-        float_height: 1.5,
+        float_height: if *crouched {
+            config.crouch_float_height
+        } else {
+            PLAYER_FLOAT_HEIGHT
+        },
+        coyote_time: config.coyote_time,
         // `TnuaBuiltinWalk` has many other fields for customizing the movement - but they have
         // sensible defaults. Refer to the `TnuaBuiltinWalk`'s documentation to learn what they do.
         ..Default::default()
     });
 
-    // Feed the jump action every frame as long as the player holds the jump button. If the player
-    // stops holding the jump button, simply stop feeding the action.
-    if keyboard.any_pressed([KeyCode::Backspace, KeyCode::Backspace]) {
+    // A jump may start in the air as an air jump. One that's already going keeps going
+    // either way.
+    let mut allow_in_air = false;
+    if keyboard.any_just_pressed(keys.jump.iter().copied())
+        && airborne
+        && air_jumps.0 < config.air_jumps
+    {
+        air_jumps.0 += 1;
+        allow_in_air = true;
+    }
+
+    // Feed the jump action every frame as long as the player holds the jump button.
+    // Otherwise simply stop feeding the action.
+    if keyboard.any_pressed(keys.jump.iter().copied()) {
         controller.action(TnuaBuiltinJump {
             // The height is the only mandatory field of the jump button.
-            height: 4.0,
+            height: config.max_jump_height,
+            shorten_extra_gravity: jump_shorten_gravity(&config, &gravity),
+            allow_in_air,
+            // A jump held in the air waits this long for the player to land.
+            input_buffer_time: config.jump_buffer_time,
             // `TnuaBuiltinJump` also has customization fields with sensible defaults.
             ..Default::default()
         });
     }
 }
 
+/// Dashes straight down along gravity onto the ground below, and shakes the camera on
+/// landing.
+fn ground_slam(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<PlayerMovementConfig>,
+    gravity: Res<Gravity>,
+    spatial_query: SpatialQuery,
+    mut shake: ResMut<CameraShake>,
+    mut player: Query<(Entity, &Transform, &mut TnuaController), With<Player>>,
+    mut slamming: Local<bool>,
+) {
+    let Ok((player, transform, mut controller)) = player.get_single_mut() else {
+        return;
+    };
+    let airborne = controller.is_airborne().unwrap_or(false);
+
+    if *slamming && !airborne {
+        *slamming = false;
+        shake.add_trauma(config.slam_trauma);
+        return;
+    }
+    if *slamming
+        || !airborne
+        || !keyboard.any_just_pressed(bindings.movement.ground_slam.iter().copied())
+    {
+        return;
+    }
+
+    // Gravity can point anywhere, so look for the ground along it rather than along -Y.
+    let Ok(down) = Dir3::new(gravity.0) else {
+        return;
+    };
+    let Some(hit) = spatial_query.cast_ray(
+        transform.translation,
+        down,
+        config.slam_max_height,
+        true,
+        SpatialQueryFilter::default().with_excluded_entities([player]),
+    ) else {
+        return;
+    };
+
+    // The dash keeps going once started, so it only needs feeding this once.
+    controller.action(TnuaBuiltinDash {
+        displacement: *down * (hit.time_of_impact - PLAYER_FLOAT_HEIGHT).max(0.0),
+        allow_in_air: true,
+        speed: config.slam_speed,
+        ..Default::default()
+    });
+    *slamming = true;
+}
+
+/// Runs after [`interpolate_player_cameras`] has rebuilt the camera's `GlobalTransform`,
+/// so the shake is added on top of wherever the camera is rendered from and never
+/// carries over to the next frame.
+fn apply_camera_shake(
+    time: Res<Time>,
+    mut shake: ResMut<CameraShake>,
+    mut camera: Query<&mut GlobalTransform, With<WorldModelCamera>>,
+) {
+    shake.trauma = (shake.trauma - shake.decay * time.delta_seconds()).clamp(0.0, 1.0);
+    if !shake.enabled || shake.trauma <= 0.0 {
+        return;
+    }
+
+    let Ok(mut global_transform) = camera.get_single_mut() else {
+        return;
+    };
+
+    let mut rng = rand::thread_rng();
+    let mut noise = || rng.gen_range(-1.0_f32..=1.0);
+    let strength = shake.trauma * shake.trauma;
+    let offset = Vec3::new(noise(), noise(), noise()) * shake.max_offset * strength;
+    let max_angle = shake.max_angle.to_radians() * strength;
+    let turn = Quat::from_euler(
+        EulerRot::YXZ,
+        noise() * max_angle,
+        noise() * max_angle,
+        noise() * max_angle,
+    );
+
+    let mut transform = global_transform.compute_transform();
+    transform.translation += transform.rotation * offset;
+    transform.rotation *= turn;
+    *global_transform = GlobalTransform::from(transform);
+}
+
+/// Extra gravity Tnua applies once the jump key is released on the way up. A jump
+/// released straight away leaves the ground at the speed for `max_jump_height`, and
+/// with this much extra gravity only reaches `min_jump_height`.
+fn jump_shorten_gravity(config: &PlayerMovementConfig, gravity: &Gravity) -> f32 {
+    let min_height = config.min_jump_height.max(0.01);
+    (gravity.0.length() * (config.max_jump_height / min_height - 1.0)).max(0.0)
+}
+
 fn player_look(
+    time: Res<Time>,
+    config: Res<PlayerInputConfig>,
+    gravity: Res<Gravity>,
+    gamepad: GamepadInput,
+    window: Query<&Window, With<PrimaryWindow>>,
     mut player: Query<&mut Transform, With<Player>>,
     mut mouse_motion: EventReader<MouseMotion>,
 ) {
@@ -195,63 +1358,360 @@ fn player_look(
         return;
     };
 
+    // The mouse only looks around while the cursor is grabbed. Its motion is still
+    // read otherwise, so it doesn't all arrive at once when the cursor is grabbed again.
+    let mouse_look = window
+        .get_single()
+        .is_ok_and(|window| window.cursor.grab_mode != CursorGrabMode::None);
+
+    let mut yaw = 0.0;
+    let mut pitch = 0.0;
     for motion in mouse_motion.read() {
-        let yaw = -motion.delta.x * 0.003;
-        let pitch = -motion.delta.y * 0.002;
-        // Order of rotations is important, see <https://gamedev.stackexchange.com/a/136175/103059>
-        player.rotate_y(yaw);
-        player.rotate_local_x(pitch);
+        if !mouse_look {
+            continue;
+        }
+        yaw -= motion.delta.x * config.yaw_sensitivity;
+        pitch -= motion.delta.y * config.pitch_sensitivity;
+    }
+
+    // Pushing the right stick up looks up, unlike the mouse whose Y grows downwards.
+    let stick = gamepad.right_stick(config.stick_dead_zone);
+    let stick_turn = config.stick_look_speed * time.delta_seconds();
+    yaw -= stick.x * stick_turn;
+    pitch += stick.y * stick_turn;
+
+    if config.invert_x {
+        yaw = -yaw;
+    }
+    if config.invert_y {
+        pitch = -pitch;
     }
+
+    let up = *gravity_up(&gravity);
+    let current_pitch = player.forward().dot(up).clamp(-1.0, 1.0).asin();
+    let pitch = clamp_pitch(current_pitch, pitch, config.max_pitch);
+
+    // Order of rotations is important, see <https://gamedev.stackexchange.com/a/136175/103059>
+    player.rotate_y(yaw);
+    player.rotate_local_x(pitch);
 }
 
-fn player_fov(
-    mut mouse_wheel: EventReader<MouseWheel>,
-    mut world_model_projection: Query<&mut Projection, With<WorldModelCamera>>,
+/// How far to pitch from `current`, in radians, to turn by `delta` without going past
+/// `max_pitch` degrees above or below the horizon. Never allows looking straight up or
+/// down, so the view can't flip over.
+fn clamp_pitch(current: f32, delta: f32, max_pitch: f32) -> f32 {
+    let max_pitch = max_pitch.to_radians().clamp(0.0, FRAC_PI_2 - 0.01);
+    (current + delta).clamp(-max_pitch, max_pitch) - current
+}
+
+/// Only the change in offset is applied to the camera, so the height set with the grow
+/// and shrink keys is kept.
+fn apply_head_bob(
+    time: Res<Time>,
+    config: Res<HeadBob>,
+    input_config: Res<PlayerInputConfig>,
+    gravity: Res<Gravity>,
+    player: Query<(&Transform, &LinearVelocity, &TnuaController), With<Player>>,
+    mut camera: Query<
+        (&mut Transform, &mut HeadBobState),
+        (With<WorldModelCamera>, Without<Player>),
+    >,
 ) {
-    let mut projection = world_model_projection.single_mut();
-    let Projection::Perspective(ref mut perspective) = projection.as_mut() else {
-        unreachable!(
-            "The `Projection` component was explicitly built with `Projection::Perspective`"
-        );
+    let (Ok((player, velocity, controller)), Ok((mut camera, mut state))) =
+        (player.get_single(), camera.get_single_mut())
+    else {
+        return;
     };
 
+    let up = *gravity_up(&gravity);
+    let horizontal_velocity = velocity.0 - up * velocity.0.dot(up);
+    let speed = horizontal_velocity.length();
+
+    let grounded = !controller.is_airborne().unwrap_or(true);
+    let target = if config.enabled && grounded && input_config.movement_speed > 0.0 {
+        (speed / input_config.movement_speed).min(2.0)
+    } else {
+        0.0
+    };
+    state.intensity +=
+        (target - state.intensity) * (config.response * time.delta_seconds()).min(1.0);
+
+    // Advance by distance walked rather than time, so the steps match the ground.
+    if grounded {
+        state.phase = (state.phase + speed * time.delta_seconds() * config.frequency * TAU) % TAU;
+    }
+
+    // Bob along gravity rather than the camera's own up, so looking down doesn't turn
+    // the bob into a push forwards.
+    let right = player.right();
+    let right = (*right - up * right.dot(up)).normalize_or_zero();
+    let world_offset = state.intensity
+        * (up * config.vertical_amplitude * state.phase.sin().abs()
+            + right * config.lateral_amplitude * state.phase.sin());
+    let offset = player.rotation.inverse() * world_offset;
+
+    camera.translation += offset - state.offset;
+    state.offset = offset;
+}
+
+/// Grabs and hides the cursor for mouse look, or releases and shows it.
+fn set_cursor_grab(window: &mut Window, grab: bool) {
+    if grab {
+        window.cursor.grab_mode = CursorGrabMode::Locked;
+        window.cursor.visible = false;
+    } else {
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    }
+}
+
+/// Whether the primary window has focus, so mouse input is meant for the player.
+fn primary_window_focused(window: Query<&Window, With<PrimaryWindow>>) -> bool {
+    window.get_single().is_ok_and(|window| window.focused)
+}
+
+fn grab_cursor(mut window: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window.get_single_mut() {
+        set_cursor_grab(&mut window, true);
+    }
+}
+
+fn toggle_cursor_grab(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !kb_input.any_just_pressed(bindings.interface.cursor_grab.iter().copied()) {
+        return;
+    }
+    let Ok(mut window) = window.get_single_mut() else {
+        return;
+    };
+
+    let grabbed = window.cursor.grab_mode != CursorGrabMode::None;
+    set_cursor_grab(&mut window, !grabbed);
+}
+
+fn toggle_third_person(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<ThirdPersonCamera>,
+) {
+    if kb_input.any_just_pressed(bindings.interface.third_person.iter().copied()) {
+        config.enabled = !config.enabled;
+    }
+}
+
+/// Only the change in offset is applied to the camera's translation, so the camera
+/// height and head bob are kept.
+fn apply_third_person(
+    config: Res<ThirdPersonCamera>,
+    mut camera: Query<(&mut Transform, &mut ThirdPersonOffset), With<WorldModelCamera>>,
+    mut body: Query<&mut RenderLayers, With<Player>>,
+    mut arm: Query<&mut Visibility, With<ViewModelArm>>,
+) {
+    let Ok((mut transform, mut applied)) = camera.get_single_mut() else {
+        return;
+    };
+
+    let offset = if config.enabled {
+        // Forward is -Z, so behind is +Z.
+        Vec3::new(0.0, config.height, config.distance)
+    } else {
+        Vec3::ZERO
+    };
+    if offset == applied.0 {
+        return;
+    }
+
+    transform.translation += offset - applied.0;
+    transform.rotation = if config.enabled {
+        Transform::from_translation(offset)
+            .looking_at(Vec3::ZERO, Vec3::Y)
+            .rotation
+    } else {
+        Quat::IDENTITY
+    };
+    applied.0 = offset;
+
+    // The body is normally only on the view model layer, where it's hidden inside the
+    // camera. In third person the world model camera needs to see it.
+    for mut layers in body.iter_mut() {
+        *layers = if config.enabled {
+            RenderLayers::layer(DEFAULT_RENDER_LAYER)
+        } else {
+            RenderLayers::layer(VIEW_MODEL_RENDER_LAYER)
+        };
+    }
+    for mut visibility in arm.iter_mut() {
+        *visibility = if config.enabled {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn lock_player_roll(gravity: Res<Gravity>, mut player: Query<&mut Transform, With<Player>>) {
+    let Ok(mut transform) = player.get_single_mut() else {
+        return;
+    };
+
+    let up = gravity_up(&gravity);
+    let forward = transform.forward();
+
+    // Looking straight along gravity, roll and yaw are the same thing, so leave it be.
+    if forward.dot(*up).abs() > 0.999 {
+        return;
+    }
+
+    // Rebuilding the rotation from the view direction and gravity's up keeps the local
+    // X axis perpendicular to gravity.
+    transform.look_to(forward, up);
+}
+
+/// Whether a crouching player has room above it to stand up.
+fn has_headroom(
+    spatial_query: &SpatialQuery,
+    gravity: &Gravity,
+    player: Entity,
+    transform: &Transform,
+    config: &PlayerMovementConfig,
+) -> bool {
+    let up = gravity_up(gravity);
+
+    // Standing raises the center by the difference in float height, and the top of the
+    // capsule is then half its length and its radius above the center. Sweep a slightly
+    // thinner sphere from the current center to where the top would be.
+    let standing_top = PLAYER_FLOAT_HEIGHT - config.crouch_float_height
+        + PLAYER_STANDING_LENGTH / 2.0
+        + PLAYER_RADIUS;
+    let probe_radius = PLAYER_RADIUS * 0.9;
+
+    spatial_query
+        .cast_shape(
+            &Collider::sphere(probe_radius),
+            transform.translation,
+            Quat::IDENTITY,
+            up,
+            standing_top - probe_radius,
+            true,
+            SpatialQueryFilter::default().with_excluded_entities([player]),
+        )
+        .is_none()
+}
+
+fn player_fov(
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut world_model_target: Query<&mut TargetFov, With<WorldModelCamera>>,
+) {
+    // Read the wheel even when there's no camera, so old events don't pile up and get
+    // applied all at once later.
+    let mut steps = 0.0_f32;
     for wheel in mouse_wheel.read() {
-        if wheel.y > 0.0 {
-            perspective.fov -= 1.0_f32.to_radians();
-            perspective.fov = perspective.fov.max(20.0_f32.to_radians());
-        } else if wheel.y < 0.0 {
-            perspective.fov += 1.0_f32.to_radians();
-            perspective.fov = perspective.fov.min(160.0_f32.to_radians());
+        // Horizontal scrolling sends events with no vertical part, and `signum` of zero
+        // is one.
+        if wheel.y != 0.0 {
+            steps += wheel.y.signum();
         }
     }
+    if steps == 0.0 {
+        return;
+    }
+
+    // Normally there's exactly one world model camera, but don't insist on it.
+    for mut target in world_model_target.iter_mut() {
+        // Scrolling up zooms in.
+        target.0 =
+            (target.0 - steps.to_radians()).clamp(MIN_FOV.to_radians(), MAX_FOV.to_radians());
+    }
+}
+
+/// Eases the world model camera's field of view towards the scroll-wheel target. The
+/// speed boost is left on top.
+fn smooth_fov_zoom(
+    time: Res<Time>,
+    mut camera: Query<(&mut Projection, &FovBoost, &TargetFov), With<WorldModelCamera>>,
+) {
+    for (mut projection, boost, target) in camera.iter_mut() {
+        let Projection::Perspective(ref mut perspective) = projection.as_mut() else {
+            continue;
+        };
+
+        let base = perspective.fov - boost.0;
+        let eased = base + (target.0 - base) * (FOV_ZOOM_RESPONSE * time.delta_seconds()).min(1.0);
+        perspective.fov = eased + boost.0;
+    }
+}
+
+/// Eases the speed boost towards the player's current speed. Only the change in boost
+/// is applied, so the scroll-wheel zoom is kept.
+fn apply_speed_fov(
+    time: Res<Time>,
+    config: Res<SpeedFov>,
+    gravity: Res<Gravity>,
+    player: Query<&LinearVelocity, With<Player>>,
+    mut camera: Query<(&mut Projection, &mut FovBoost), With<WorldModelCamera>>,
+) {
+    let (Ok(velocity), Ok((mut projection, mut boost))) =
+        (player.get_single(), camera.get_single_mut())
+    else {
+        return;
+    };
+    let Projection::Perspective(ref mut perspective) = projection.as_mut() else {
+        return;
+    };
+
+    // Falling shouldn't widen the view, only moving across the ground.
+    let up = -gravity.0.normalize_or_zero();
+    let horizontal_speed = (velocity.0 - up * velocity.0.dot(up)).length();
+
+    let target = if config.enabled && config.full_boost_speed > 0.0 {
+        let t = (horizontal_speed / config.full_boost_speed).clamp(0.0, 1.0);
+        config.max_boost.to_radians() * t.powf(config.curve)
+    } else {
+        0.0
+    };
+    let eased = boost.0 + (target - boost.0) * (config.response * time.delta_seconds()).min(1.0);
+
+    let base = perspective.fov - boost.0;
+    let fov = (base + eased).clamp(MIN_FOV.to_radians(), MAX_FOV.to_radians());
+    boost.0 = fov - base;
+    perspective.fov = fov;
 }
 
+/// Only the change in offset is applied to the camera, so the head bob and third-person
+/// offset are kept.
 fn player_grow_shrink(
-    mut transform: Query<&mut Transform, With<WorldModelCamera>>,
+    mut camera: Query<(&mut Transform, &mut HeadOffset), With<WorldModelCamera>>,
     time: Res<Time>,
     kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<PlayerInputConfig>,
 ) {
-    let Ok(mut transform) = transform.get_single_mut() else {
+    let Ok((mut transform, mut head_offset)) = camera.get_single_mut() else {
         return;
     };
 
     let mut direction = Vec3::ZERO;
 
-    if kb_input.pressed(KeyCode::Minus) {
+    if kb_input.any_pressed(bindings.movement.shrink.iter().copied()) {
         direction.y -= 1.;
     }
 
-    if kb_input.pressed(KeyCode::Equal) {
+    if kb_input.any_pressed(bindings.movement.grow.iter().copied()) {
         direction.y += 1.;
     }
 
     // Progressively update the player's position over time. Normalize the
     // direction vector to prevent it from exceeding a magnitude of 1 when
     // moving diagonally.
-    let move_delta = direction.normalize_or_zero() * PLAYER_SPEED * time.delta_seconds();
+    let move_delta = direction.normalize_or_zero() * config.movement_speed * time.delta_seconds();
 
-    // Apply the movement to the player's translation
-    transform.translation += move_delta;
+    // Apply the movement to the camera's height, keeping it inside the body.
+    let offset = (head_offset.0 + move_delta.y).clamp(MIN_HEAD_OFFSET, MAX_HEAD_OFFSET);
+    transform.translation.y += offset - head_offset.0;
+    head_offset.0 = offset;
 }
 
 pub fn add_player_actions(app: &mut App) -> &mut App {
@@ -260,3 +1720,41 @@ pub fn add_player_actions(app: &mut App) -> &mut App {
         (player_look, player_fov, player_move, player_grow_shrink),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_pitch_stops_at_max_pitch() {
+        let max_pitch: f32 = 80.0;
+        let limit = max_pitch.to_radians() + 1e-5;
+
+        let mut pitch = 0.0;
+        for _ in 0..100 {
+            pitch += clamp_pitch(pitch, 0.5, max_pitch);
+            assert!(pitch <= limit, "looked up to {pitch} rad");
+        }
+        for _ in 0..100 {
+            pitch += clamp_pitch(pitch, -0.5, max_pitch);
+            assert!(pitch >= -limit, "looked down to {pitch} rad");
+        }
+    }
+
+    #[test]
+    fn clamp_pitch_never_reaches_straight_up_or_down() {
+        for max_pitch in [90.0, 120.0, 360.0] {
+            assert!(clamp_pitch(0.0, 10.0, max_pitch) < FRAC_PI_2);
+            assert!(clamp_pitch(0.0, -10.0, max_pitch) > -FRAC_PI_2);
+        }
+    }
+
+    #[test]
+    fn clamp_pitch_brings_back_a_pitch_past_the_limit() {
+        // Lowering the limit while looking further up turns the view back down to it.
+        let max_pitch: f32 = 45.0;
+        let current = 60.0_f32.to_radians();
+        let pitch = current + clamp_pitch(current, 0.0, max_pitch);
+        assert!((pitch - max_pitch.to_radians()).abs() < 1e-5);
+    }
+}
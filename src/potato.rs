@@ -1,3 +1,4 @@
+use avian3d::prelude::*;
 use bevy::prelude::*;
 use bevy::render::{render_asset::RenderAssetUsages, render_resource::PrimitiveTopology};
 use rand::Rng;
@@ -6,7 +7,83 @@ pub struct PotatoPlugin;
 
 impl Plugin for PotatoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup);
+        app.init_resource::<PotatoConfig>()
+            .add_systems(Startup, setup);
+    }
+}
+
+/// Where the potato is placed.
+#[derive(Resource, Debug, Clone)]
+pub struct PotatoConfig {
+    pub position: Vec3,
+    /// Rotation applied to the generated mesh, which is elongated along its local X
+    /// axis. Use it to pick which side of the potato faces up.
+    pub initial_rotation: Quat,
+    /// Scale the potato so its longest side is this long, in world units. Takes
+    /// precedence over `scale`.
+    pub target_size: Option<f32>,
+    /// Scale applied to the generated mesh when there's no `target_size`.
+    pub scale: f32,
+    /// Let the potato fall and roll around instead of staying put.
+    pub dynamic: bool,
+    /// Density of a dynamic potato, which its mass is computed from.
+    pub density: f32,
+}
+
+impl Default for PotatoConfig {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(4.0, 4.0, 4.0),
+            initial_rotation: Quat::IDENTITY,
+            target_size: None,
+            scale: 1.0,
+            dynamic: false,
+            density: 1.0,
+        }
+    }
+}
+
+/// Marks a potato.
+#[derive(Debug, Component)]
+pub struct Potato;
+
+/// The mesh and material every potato shares, so spawning more doesn't generate or
+/// upload them again.
+#[derive(Resource, Debug, Clone)]
+pub struct PotatoAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    /// Exact collider for static potatoes.
+    pub static_collider: Option<Collider>,
+    /// Convex pieces for dynamic potatoes, since dynamic trimeshes are unstable. The
+    /// decomposition is slow, so it's only built once a dynamic potato is spawned.
+    dynamic_collider: Option<Collider>,
+    /// The indexed mesh the colliders are built from.
+    collider_mesh: Mesh,
+    /// Length of the longest side of the unscaled mesh.
+    pub extent: f32,
+}
+
+impl PotatoAssets {
+    /// Scale that makes a potato `size` long along its longest side.
+    pub fn scale_for_size(&self, size: f32) -> f32 {
+        if self.extent > 0.0 {
+            size / self.extent
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the collider for dynamic potatoes, building it the first time it's
+    /// requested.
+    pub fn dynamic_collider(&mut self) -> Option<Collider> {
+        if self.dynamic_collider.is_none() {
+            self.dynamic_collider = Collider::convex_decomposition_from_mesh(&self.collider_mesh);
+            if self.dynamic_collider.is_none() {
+                error!("Failed to build a collider for the dynamic potato");
+            }
+        }
+        self.dynamic_collider.clone()
     }
 }
 
@@ -14,18 +91,83 @@ fn setup(
     mut commands: Commands, 
     mut meshes: ResMut<Assets<Mesh>>, 
     mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<PotatoConfig>,
 ) {
-    let potato_mesh = generate_potato_mesh(64, 16, 0.05, 2.0);
+    // The colliders need the indexed mesh, before flat shading drops the indices.
+    let collider_mesh = generate_potato_mesh(64, 16, 0.05, 2.0);
+    let extent = collider_mesh
+        .compute_aabb()
+        .map_or(0.0, |aabb| aabb.half_extents.max_element() * 2.0);
+    let static_collider = Collider::trimesh_from_mesh(&collider_mesh);
+    if static_collider.is_none() {
+        error!("Failed to build a collider for the potato");
+    }
 
-    commands.spawn(PbrBundle {
+    let potato_mesh = collider_mesh
+        .clone()
+        .with_duplicated_vertices()
+        .with_computed_flat_normals();
+    let mut assets = PotatoAssets {
         mesh: meshes.add(potato_mesh),
         material: materials.add(StandardMaterial {
             base_color: Color::srgb(0.8, 0.5, 0.3),
             ..Default::default()
         }),
-        transform: Transform::from_xyz(4.0, 4.0, 4.0),
-        ..Default::default()
-    });
+        static_collider,
+        dynamic_collider: None,
+        collider_mesh,
+        extent,
+    };
+
+    let scale = config
+        .target_size
+        .map_or(config.scale, |size| assets.scale_for_size(size));
+
+    spawn_potato(
+        &mut commands,
+        &mut assets,
+        Transform::from_translation(config.position)
+            .with_rotation(config.initial_rotation)
+            .with_scale(Vec3::splat(scale)),
+        config.dynamic.then_some(config.density),
+    );
+    commands.insert_resource(assets);
+}
+
+/// Spawns another potato at `transform`, which also sets its size through its scale.
+/// With a `density` it's a dynamic body of that density, otherwise it's static.
+pub fn spawn_potato(
+    commands: &mut Commands,
+    assets: &mut PotatoAssets,
+    transform: Transform,
+    density: Option<f32>,
+) -> Entity {
+    let mut potato = commands.spawn((
+        Potato,
+        PbrBundle {
+            mesh: assets.mesh.clone(),
+            material: assets.material.clone(),
+            transform,
+            ..Default::default()
+        },
+    ));
+
+    match density {
+        Some(density) => {
+            potato.insert((RigidBody::Dynamic, ColliderDensity(density)));
+            if let Some(collider) = assets.dynamic_collider() {
+                potato.insert(collider);
+            }
+        }
+        None => {
+            potato.insert(RigidBody::Static);
+            if let Some(collider) = &assets.static_collider {
+                potato.insert(collider.clone());
+            }
+        }
+    }
+
+    potato.id()
 }
 
 fn generate_potato_mesh(
@@ -84,7 +226,5 @@ fn generate_potato_mesh(
     )
     .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertices)
     .with_inserted_indices(bevy::render::mesh::Indices::U32(indices))
-    .with_duplicated_vertices()
-    .with_computed_flat_normals()
 
 }
@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+use crate::bindings::KeyBindings;
+
+pub struct ResetPlugin;
+
+impl Plugin for ResetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetWorld>()
+            .configure_sets(Update, (ResetSet::Despawn, ResetSet::Respawn).chain())
+            .add_systems(Update, send_reset_world.before(ResetSet::Despawn));
+    }
+}
+
+/// Puts the world back into the state of a fresh launch.
+#[derive(Event, Debug, Clone, Default)]
+pub struct ResetWorld;
+
+/// Systems reacting to [`ResetWorld`]. Everything in `Despawn` runs (and has its
+/// commands applied) before anything in `Respawn`, so nothing respawned can reference
+/// an entity that's about to go away.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResetSet {
+    Despawn,
+    Respawn,
+}
+
+fn send_reset_world(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut reset: EventWriter<ResetWorld>,
+) {
+    if kb_input.any_just_pressed(bindings.world.reset.iter().copied()) {
+        reset.send(ResetWorld);
+    }
+}
@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::RenderConfig;
+use crate::lighting::{DayNightCycle, DistanceFog, LightingConfig};
+use crate::player::{
+    CameraShake, FallDamage, HeadBob, PlayerInputConfig, PlayerMovementConfig, SpeedFov,
+    ThirdPersonCamera,
+};
+use crate::terrain::TerrainConfig;
+use crate::turbine::{TurbineConfig, TurbineRingConfig};
+
+/// Seconds the settings have to stay the same before they're saved, so dragging a value
+/// around doesn't rewrite the file every frame.
+const SAVE_DELAY: f32 = 1.0;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsConfig>()
+            .add_systems(PreStartup, load_settings)
+            .add_systems(
+                Last,
+                (
+                    save_changed_settings,
+                    save_settings.run_if(on_event::<AppExit>()),
+                ),
+            );
+    }
+}
+
+/// Where the settings are kept between runs.
+#[derive(Resource, Debug, Clone)]
+pub struct SettingsConfig {
+    pub path: PathBuf,
+    /// Write the settings back to `path` shortly after they change, and when the app
+    /// exits.
+    pub autosave: bool,
+}
+
+impl Default for SettingsConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("settings.toml"),
+            autosave: true,
+        }
+    }
+}
+
+/// The tunables kept in the settings file, one table per resource. Runtime state, like
+/// the time of day, the wind or which debug views are open, is left out.
+///
+/// Anything missing from the file keeps its default, so older files still load.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub input: PlayerInputConfig,
+    pub movement: PlayerMovementConfig,
+    pub head_bob: HeadBob,
+    pub speed_fov: SpeedFov,
+    pub fall_damage: FallDamage,
+    pub camera_shake: CameraShake,
+    pub third_person: ThirdPersonCamera,
+    pub render: RenderConfig,
+    pub lighting: LightingConfig,
+    pub fog: DistanceFog,
+    pub day_night: DayNightCycle,
+    pub terrain: TerrainConfig,
+    pub turbine: TurbineConfig,
+    pub turbine_ring: TurbineRingConfig,
+}
+
+impl Settings {
+    /// The current settings. Resources that don't exist are taken as their defaults.
+    pub fn capture(world: &World) -> Self {
+        fn get<T: Resource + Clone + Default>(world: &World) -> T {
+            world.get_resource::<T>().cloned().unwrap_or_default()
+        }
+
+        Self {
+            input: get(world),
+            movement: get(world),
+            head_bob: get(world),
+            speed_fov: get(world),
+            fall_damage: get(world),
+            camera_shake: get(world),
+            third_person: get(world),
+            render: get(world),
+            lighting: get(world),
+            fog: get(world),
+            day_night: get(world),
+            terrain: get(world),
+            turbine: get(world),
+            turbine_ring: get(world),
+        }
+    }
+
+    /// Replaces the resources with these settings. The runtime state in them is reset,
+    /// so this is only meant for before anything is spawned.
+    pub fn apply(self, world: &mut World) {
+        world.insert_resource(self.input);
+        world.insert_resource(self.movement);
+        world.insert_resource(self.head_bob);
+        world.insert_resource(self.speed_fov);
+        world.insert_resource(self.fall_damage);
+        world.insert_resource(self.camera_shake);
+        world.insert_resource(self.third_person);
+        world.insert_resource(self.render);
+        world.insert_resource(self.lighting);
+        world.insert_resource(self.fog);
+        world.insert_resource(self.day_night);
+        world.insert_resource(self.terrain);
+        world.insert_resource(self.turbine);
+        world.insert_resource(self.turbine_ring);
+    }
+}
+
+/// Applies the settings file over the defaults, before anything is spawned. A missing
+/// file leaves the defaults, and so does one that can't be parsed, with a warning.
+fn load_settings(world: &mut World) {
+    let path = world.resource::<SettingsConfig>().path.clone();
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+        Err(error) => {
+            warn!("Couldn't read settings from {}: {error}", path.display());
+            return;
+        }
+    };
+
+    match toml::from_str::<Settings>(&contents) {
+        Ok(settings) => {
+            settings.apply(world);
+            info!("Loaded settings from {}", path.display());
+        }
+        Err(error) => warn!("Ignoring settings in {}: {error}", path.display()),
+    }
+}
+
+/// Saves the settings once they've stopped changing for [`SAVE_DELAY`] seconds, so
+/// they survive the app being killed rather than closed.
+fn save_changed_settings(
+    world: &mut World,
+    mut seen: Local<Option<Settings>>,
+    mut pending: Local<Option<Timer>>,
+) {
+    if !world.resource::<SettingsConfig>().autosave {
+        return;
+    }
+
+    // Settings are compared by value, since some of the resources also hold runtime
+    // state that changes every frame.
+    let settings = Settings::capture(world);
+    if seen.as_ref() != Some(&settings) {
+        // The first settings seen are the loaded ones, which don't need saving.
+        if seen.is_some() {
+            *pending = Some(Timer::from_seconds(SAVE_DELAY, TimerMode::Once));
+        }
+        *seen = Some(settings);
+        return;
+    }
+
+    // Real time, so settings changed from the pause menu still get saved.
+    let delta = world.resource::<Time<Real>>().delta();
+    if pending
+        .as_mut()
+        .is_some_and(|timer| timer.tick(delta).finished())
+    {
+        *pending = None;
+        write_settings(world, &settings);
+    }
+}
+
+fn save_settings(world: &mut World) {
+    if world.resource::<SettingsConfig>().autosave {
+        write_settings(world, &Settings::capture(world));
+    }
+}
+
+fn write_settings(world: &World, settings: &Settings) {
+    let path = &world.resource::<SettingsConfig>().path;
+    let result = toml::to_string_pretty(settings)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| std::fs::write(path, contents).map_err(|error| error.to_string()));
+    if let Err(error) = result {
+        warn!("Couldn't save settings to {}: {error}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = Settings::default();
+        settings.input.invert_y = true;
+        settings.turbine.model = Some("models/turbine.gltf".to_string());
+        settings.turbine_ring.lifetime = 30.0;
+
+        let contents = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&contents).unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_settings_keep_their_defaults() {
+        let settings: Settings = toml::from_str("[input]\nmax_pitch = 60.0\n").unwrap();
+        assert_eq!(settings.input.max_pitch, 60.0);
+        assert_eq!(
+            settings.input.yaw_sensitivity,
+            PlayerInputConfig::default().yaw_sensitivity
+        );
+        assert_eq!(settings.movement, PlayerMovementConfig::default());
+    }
+
+    #[test]
+    fn runtime_state_isnt_saved() {
+        let mut settings = Settings::default();
+        settings.camera_shake.trauma = 1.0;
+        settings.third_person.enabled = true;
+
+        let contents = toml::to_string_pretty(&settings).unwrap();
+        assert!(!contents.contains("trauma"));
+        assert_eq!(
+            toml::from_str::<Settings>(&contents).unwrap(),
+            Settings::default()
+        );
+    }
+}
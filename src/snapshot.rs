@@ -0,0 +1,167 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::Player;
+use crate::turbine::Turbine;
+
+/// The state of the sandbox that physics regression checks care about.
+///
+/// With a seeded RNG and a fixed physics timestep, running the same number of steps
+/// should always produce a snapshot that [`matches`](WorldSnapshot::matches) a saved
+/// one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    /// `None` when there's no player.
+    pub player_position: Option<Vec3>,
+    pub player_up: Option<Vec3>,
+    /// Centers of the turbine towers, sorted so the order doesn't depend on entity ids.
+    pub turbine_positions: Vec<Vec3>,
+    pub gravity: Vec3,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let player = world
+            .query_filtered::<&Transform, With<Player>>()
+            .get_single(world)
+            .ok()
+            .copied();
+
+        let mut turbine_positions: Vec<Vec3> = world
+            .query_filtered::<&Transform, With<Turbine>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+        turbine_positions.sort_by(|a, b| {
+            a.x.total_cmp(&b.x)
+                .then(a.y.total_cmp(&b.y))
+                .then(a.z.total_cmp(&b.z))
+        });
+
+        Self {
+            player_position: player.map(|transform| transform.translation),
+            player_up: player.map(|transform| *transform.up()),
+            turbine_positions,
+            gravity: world.get_resource::<Gravity>().map_or(Vec3::ZERO, |g| g.0),
+        }
+    }
+
+    /// Whether every position and direction in `other` is within `tolerance` of this
+    /// snapshot's.
+    pub fn matches(&self, other: &Self, tolerance: f32) -> bool {
+        let close = |a: Vec3, b: Vec3| a.distance(b) <= tolerance;
+        let close_option = |a: Option<Vec3>, b: Option<Vec3>| match (a, b) {
+            (Some(a), Some(b)) => close(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+
+        close_option(self.player_position, other.player_position)
+            && close_option(self.player_up, other.player_up)
+            && close(self.gravity, other.gravity)
+            && self.turbine_positions.len() == other.turbine_positions.len()
+            && self
+                .turbine_positions
+                .iter()
+                .zip(&other.turbine_positions)
+                .all(|(a, b)| close(*a, *b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// A ball standing in for the player, dropped onto a slab next to a turbine tower.
+    fn simulate(steps: usize) -> WorldSnapshot {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 64.0,
+        )));
+        app.finish();
+        app.cleanup();
+
+        let world = app.world_mut();
+        world.spawn((
+            TransformBundle::default(),
+            RigidBody::Static,
+            Collider::cuboid(20.0, 0.1, 20.0),
+        ));
+        world.spawn((
+            Player,
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 3.0, 0.0)),
+            RigidBody::Dynamic,
+            Collider::sphere(0.5),
+        ));
+        world.spawn((
+            Turbine { tower_height: 8.0 },
+            TransformBundle::from_transform(Transform::from_xyz(5.0, 4.05, 0.0)),
+            RigidBody::Static,
+            Collider::cylinder(0.3, 8.0),
+        ));
+
+        for _ in 0..steps {
+            app.update();
+        }
+        WorldSnapshot::capture(app.world_mut())
+    }
+
+    #[test]
+    fn capture_fills_every_field() {
+        let snapshot = simulate(128);
+
+        // The ball has come to rest on the slab.
+        let player_position = snapshot.player_position.unwrap();
+        assert!(
+            (player_position.y - 0.55).abs() < 0.05,
+            "the player ended up at {player_position}"
+        );
+        assert!(snapshot.player_up.is_some());
+        assert_eq!(snapshot.turbine_positions.len(), 1);
+        assert!(snapshot.turbine_positions[0].distance(Vec3::new(5.0, 4.05, 0.0)) < 1e-4);
+        assert_eq!(snapshot.gravity, Gravity::default().0);
+    }
+
+    #[test]
+    fn same_steps_give_matching_snapshots() {
+        assert!(simulate(64).matches(&simulate(64), 1e-4));
+    }
+
+    #[test]
+    fn matches_uses_the_tolerance() {
+        let snapshot = WorldSnapshot {
+            player_position: Some(Vec3::new(0.0, 1.0, 0.0)),
+            player_up: Some(Vec3::Y),
+            turbine_positions: vec![Vec3::new(5.0, 4.0, 0.0)],
+            gravity: Vec3::NEG_Y * 9.81,
+        };
+
+        let mut nearby = snapshot.clone();
+        nearby.player_position = Some(Vec3::new(0.0, 1.05, 0.0));
+        nearby.turbine_positions[0].x += 0.05;
+        assert!(snapshot.matches(&nearby, 0.1));
+
+        let mut moved = snapshot.clone();
+        moved.turbine_positions[0].x += 0.2;
+        assert!(!snapshot.matches(&moved, 0.1));
+
+        let mut missing_player = snapshot.clone();
+        missing_player.player_position = None;
+        assert!(!snapshot.matches(&missing_player, 0.1));
+
+        let mut extra_turbine = snapshot.clone();
+        extra_turbine.turbine_positions.push(Vec3::ZERO);
+        assert!(!snapshot.matches(&extra_turbine, 0.1));
+    }
+}
@@ -1,26 +1,228 @@
 use bevy::color::palettes::tailwind;
 use bevy::render::{render_asset::RenderAssetUsages, render_resource::PrimitiveTopology};
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
 use bevy::{color::palettes::css, prelude::*};
 
 use avian3d::prelude::*;
 use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::KeyBindings;
+use crate::hud::{HudElement, HudRegion};
+use crate::physics::describe_mesh;
+use crate::player::{Player, PLAYER_SPAWN};
+
+/// Marks every terrain chunk.
+#[derive(Debug, Component)]
+pub struct Terrain;
+
+/// A square piece of the terrain, at this position in the grid of chunks.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct TerrainChunk(pub IVec2);
+
+/// A terrain collider being built off the main thread.
+///
+/// A new chunk has no collider until the task finishes. A placeholder would have to
+/// cover the whole chunk at some guessed height, pushing up anything below it.
+#[derive(Component)]
+pub struct TerrainColliderTask(Task<Result<Collider, String>>);
+
+/// Marks the water covering the low parts of the terrain.
+#[derive(Debug, Component)]
+pub struct Water;
+
+/// Shown while a terrain collider is being rebuilt.
+#[derive(Component)]
+struct TerrainStatusText;
+
+/// How the terrain is generated. Changes apply the next time it's regenerated.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainConfig {
+    /// Number of vertices along each side of a chunk, one unit apart. Neighbouring
+    /// chunks share their edge vertices.
+    pub size: usize,
+    /// How many chunks out from the player's chunk to keep loaded, in each direction.
+    pub view_distance: u32,
+    /// How far apart neighbouring vertices are in noise space. Larger values give
+    /// busier terrain.
+    pub scale: f64,
+    pub noise: TerrainNoise,
+    pub bands: TerrainBands,
+    /// Height of the water, as a fraction of the noise amplitude like the bands.
+    pub sea_level: f32,
+    /// Shade the terrain smoothly instead of showing each triangle as a flat facet.
+    pub smooth_normals: bool,
+}
+
+impl TerrainConfig {
+    /// Width of a chunk in world units.
+    fn chunk_extent(&self) -> i32 {
+        self.size.saturating_sub(1).max(1) as i32
+    }
+
+    /// Position of the corner of `chunk` with the lowest coordinates. Chunk (0, 0) is
+    /// centered on the origin.
+    fn chunk_origin(&self, chunk: IVec2) -> IVec2 {
+        let extent = self.chunk_extent();
+        chunk * extent - IVec2::splat(extent / 2)
+    }
+
+    /// The chunk containing `position`.
+    fn chunk_at(&self, position: Vec3) -> IVec2 {
+        let extent = self.chunk_extent();
+        ((position.xz() + (extent / 2) as f32) / extent as f32)
+            .floor()
+            .as_ivec2()
+    }
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            size: 64,
+            view_distance: 2,
+            scale: 0.2,
+            noise: TerrainNoise::default(),
+            bands: TerrainBands::default(),
+            sea_level: -0.25,
+            smooth_normals: false,
+        }
+    }
+}
+
+/// Fractal noise shaping the terrain: several octaves of Perlin noise, each finer and
+/// fainter than the last.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainNoise {
+    pub seed: u32,
+    pub octaves: usize,
+    /// How much finer each octave is than the one before.
+    pub lacunarity: f64,
+    /// How much fainter each octave is than the one before.
+    pub persistence: f64,
+    /// Height of the tallest possible peak, and depth of the deepest valley.
+    pub amplitude: f32,
+}
+
+impl Default for TerrainNoise {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            octaves: 4,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            amplitude: 1.5,
+        }
+    }
+}
+
+impl TerrainNoise {
+    /// Height of the terrain at `point`, in noise space.
+    fn height(&self, perlin: &Perlin, point: [f64; 2]) -> f32 {
+        let (mut total, mut max) = (0.0, 0.0);
+        let (mut frequency, mut weight) = (1.0, 1.0);
+        for _ in 0..self.octaves {
+            total += perlin.get([point[0] * frequency, point[1] * frequency]) * weight;
+            max += weight;
+            frequency *= self.lacunarity;
+            weight *= self.persistence;
+        }
+
+        // Normalized so the amplitude doesn't depend on the number of octaves.
+        if max > 0.0 {
+            (total / max) as f32 * self.amplitude
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Heights where the terrain color changes, as fractions of the noise amplitude from -1
+/// (the deepest valley) to 1 (the tallest peak). Below `sand` is water.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainBands {
+    pub sand: f32,
+    pub grass: f32,
+    pub rock: f32,
+    pub snow: f32,
+}
+
+impl Default for TerrainBands {
+    fn default() -> Self {
+        Self {
+            sand: -0.2,
+            grass: -0.05,
+            rock: 0.45,
+            snow: 0.7,
+        }
+    }
+}
+
+impl TerrainBands {
+    /// Color of the terrain at `height`, given as a fraction of the noise amplitude.
+    fn color(&self, height: f32) -> Color {
+        let color = if height >= self.snow {
+            tailwind::SLATE_50
+        } else if height >= self.rock {
+            tailwind::STONE_500
+        } else if height >= self.grass {
+            tailwind::LIME_500
+        } else if height >= self.sand {
+            tailwind::AMBER_200
+        } else {
+            tailwind::BLUE_600
+        };
+        Color::from(color)
+    }
+}
+
+/// Generates a `size` by `size` grid of terrain, sampling the noise from `origin` so
+/// that neighbouring chunks line up. The normals are smooth.
+fn generate_procedural_terrain_mesh(
+    origin: IVec2,
+    size: usize,
+    scale: f64,
+    noise: &TerrainNoise,
+    bands: &TerrainBands,
+) -> Mesh {
+    let perlin = Perlin::new(noise.seed);
+    let height_at = |x: i32, z: i32| {
+        let sample = [(origin.x + x) as f64 * scale, (origin.y + z) as f64 * scale];
+        noise.height(&perlin, sample)
+    };
 
-fn generate_procedural_terrain_mesh(size: usize, scale: f64) -> Mesh {
-    let perlin = Perlin::new(42);
     let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
     let mut indices = Vec::new();
 
     // Generate vertices
-    for z in 0..size {
-        for x in 0..size {
-            let height = perlin.get([x as f64 * scale, z as f64 * scale]) as f32;
+    for z in 0..size as i32 {
+        for x in 0..size as i32 {
+            let height = height_at(x, z);
             vertices.push([x as f32, height, z as f32]);
+
+            // The slope comes from the noise rather than the neighbouring triangles, so
+            // the normals along the edges match the next chunk's.
+            let dx = (height_at(x + 1, z) - height_at(x - 1, z)) / 2.0;
+            let dz = (height_at(x, z + 1) - height_at(x, z - 1)) / 2.0;
+            normals.push(Vec3::new(-dx, 1.0, -dz).normalize().to_array());
+
+            let relative_height = if noise.amplitude != 0.0 {
+                height / noise.amplitude
+            } else {
+                0.0
+            };
+            colors.push(bands.color(relative_height).to_linear().to_f32_array());
         }
     }
 
     // Generate indices
-    for z in 0..(size - 1) {
-        for x in 0..(size - 1) {
+    for z in 0..size.saturating_sub(1) {
+        for x in 0..size.saturating_sub(1) {
             let i = z * size + x;
 
             // First triangle of the quad
@@ -40,9 +242,9 @@ fn generate_procedural_terrain_mesh(size: usize, scale: f64) -> Mesh {
         RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
     )
     .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertices)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
     .with_inserted_indices(bevy::render::mesh::Indices::U32(indices))
-    .with_duplicated_vertices()
-    .with_computed_flat_normals()
 }
 
 pub fn setup_terrain(
@@ -50,20 +252,16 @@ pub fn setup_terrain(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // Generate procedural terrain mesh
-    let terrain_mesh = generate_procedural_terrain_mesh(100, 0.2);
-
-    // Spawn terrain entity
     commands.spawn((
-        RigidBody::Static,
-        Collider::half_space(Vec3::Y),
-        //Collider::convex_decomposition_from_mesh(&terrain_mesh).unwrap(),
-        PbrBundle {
-            mesh: meshes.add(terrain_mesh),
-            material: materials.add(Color::from(tailwind::LIME_500)),
-            transform: Transform::from_xyz(-50.0, 0.0, -50.0),
-            ..Default::default()
-        },
+        TerrainStatusText,
+        TextBundle::from_section(
+            "Regenerating terrain...",
+            TextStyle {
+                font_size: 20.0,
+                ..default()
+            },
+        ),
+        HudElement::new(HudRegion::TopRight),
     ));
 
     // Spawn a little platform for the player to jump on.
@@ -78,3 +276,179 @@ pub fn setup_terrain(
         Collider::cuboid(4.0, 1.0, 4.0),
     ));
 }
+
+fn spawn_terrain_chunk(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    config: &TerrainConfig,
+    chunk: IVec2,
+) {
+    let extent = config.chunk_extent() as f32;
+    let origin = config.chunk_origin(chunk);
+
+    // Generate procedural terrain mesh. The collider has to be built from the indexed
+    // mesh, since flat shading duplicates the vertices and drops the indices.
+    let collider_mesh = generate_procedural_terrain_mesh(
+        origin,
+        config.size,
+        config.scale,
+        &config.noise,
+        &config.bands,
+    );
+    let terrain_mesh = if config.smooth_normals {
+        collider_mesh.clone()
+    } else {
+        collider_mesh
+            .clone()
+            .with_duplicated_vertices()
+            .with_computed_flat_normals()
+    };
+
+    // Spawn terrain entity. It gets its collider once the trimesh has been built.
+    let terrain = commands
+        .spawn((
+            Terrain,
+            TerrainChunk(chunk),
+            RigidBody::Static,
+            PbrBundle {
+                mesh: meshes.add(terrain_mesh),
+                // White, so the vertex colors show through unchanged.
+                material: materials.add(Color::WHITE),
+                transform: Transform::from_xyz(origin.x as f32, 0.0, origin.y as f32),
+                ..Default::default()
+            },
+        ))
+        .id();
+    rebuild_terrain_collider(commands, terrain, collider_mesh);
+
+    // The water is a child of the chunk, so it's replaced along with it. It has no
+    // collider, so things sink through it to the ground.
+    let water = commands
+        .spawn((
+            Water,
+            PbrBundle {
+                mesh: meshes.add(Plane3d::default().mesh().size(extent, extent)),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::srgba(0.1, 0.35, 0.8, 0.6),
+                    alpha_mode: AlphaMode::Blend,
+                    perceptual_roughness: 0.1,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(
+                    extent / 2.0,
+                    config.sea_level * config.noise.amplitude,
+                    extent / 2.0,
+                ),
+                ..default()
+            },
+        ))
+        .id();
+    commands.entity(terrain).add_child(water);
+}
+
+/// Spawns the terrain chunks within the view distance of the player, and despawns the
+/// ones that have fallen out of it. Until the player exists, the chunks around
+/// [`PLAYER_SPAWN`] are loaded, so it can also run at startup.
+pub fn stream_terrain_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<TerrainConfig>,
+    player: Query<&Transform, With<Player>>,
+    chunks: Query<(Entity, &TerrainChunk)>,
+) {
+    let position = player
+        .get_single()
+        .map_or(PLAYER_SPAWN, |transform| transform.translation);
+    let center = config.chunk_at(position);
+    let view_distance = config.view_distance as i32;
+    let in_view = |chunk: IVec2| (chunk - center).abs().max_element() <= view_distance;
+
+    let mut loaded = Vec::new();
+    for (entity, chunk) in chunks.iter() {
+        if in_view(chunk.0) {
+            loaded.push(chunk.0);
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for z in -view_distance..=view_distance {
+        for x in -view_distance..=view_distance {
+            let chunk = center + IVec2::new(x, z);
+            if !loaded.contains(&chunk) {
+                spawn_terrain_chunk(&mut commands, &mut meshes, &mut materials, &config, chunk);
+            }
+        }
+    }
+}
+
+/// Replaces the terrain with a freshly generated one using a new random seed.
+pub fn regenerate_terrain(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<TerrainConfig>,
+    terrain: Query<Entity, With<Terrain>>,
+) {
+    if !kb_input.any_just_pressed(bindings.world.regenerate_terrain.iter().copied()) {
+        return;
+    }
+
+    // Despawning the chunks also drops their colliders and any collider still being
+    // built. `stream_terrain_chunks` spawns new ones right after.
+    for entity in terrain.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    config.noise.seed = rand::random();
+    info!("Regenerating terrain with seed {}", config.noise.seed);
+}
+
+/// Starts building a collider for `mesh` in the background. The result is given to the
+/// terrain once [`swap_terrain_collider`] sees the task finish.
+pub fn rebuild_terrain_collider(commands: &mut Commands, terrain: Entity, mesh: Mesh) {
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        Collider::trimesh_from_mesh(&mesh).ok_or_else(|| describe_mesh(&mesh))
+    });
+    commands.entity(terrain).insert(TerrainColliderTask(task));
+}
+
+pub fn swap_terrain_collider(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut TerrainColliderTask)>,
+) {
+    for (entity, mut task) in tasks.iter_mut() {
+        let Some(collider) = block_on(poll_once(&mut task.0)) else {
+            continue;
+        };
+
+        let mut terrain = commands.entity(entity);
+        terrain.remove::<TerrainColliderTask>();
+        match collider {
+            Ok(collider) => {
+                terrain.insert(collider);
+            }
+            Err(mesh) => error!(
+                "Failed to build a trimesh collider for the terrain from {mesh}, \
+                 leaving the chunk without one"
+            ),
+        }
+    }
+}
+
+pub fn show_terrain_status(
+    tasks: Query<(), With<TerrainColliderTask>>,
+    mut status: Query<&mut Visibility, With<TerrainStatusText>>,
+) {
+    let Ok(mut visibility) = status.get_single_mut() else {
+        return;
+    };
+
+    *visibility = if tasks.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use avian3d::prelude::*;
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+
+use crate::bindings::KeyBindings;
+use crate::console_field;
+use crate::player::Player;
+use crate::reset::{ResetSet, ResetWorld};
+
+/// How far below the player's center the trail is drawn, so it hugs the ground.
+const TRAIL_GROUND_OFFSET: f32 = 1.4;
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrailConfig>()
+            .init_resource::<Breadcrumbs>()
+            .add_systems(
+                Update,
+                (
+                    toggle_trail,
+                    record_breadcrumbs,
+                    draw_breadcrumbs.run_if(|config: Res<TrailConfig>| config.enabled),
+                    clear_breadcrumbs
+                        .in_set(ResetSet::Despawn)
+                        .run_if(on_event::<ResetWorld>()),
+                ),
+            );
+
+        console_field!(app, "trail.enabled", TrailConfig, enabled);
+        console_field!(app, "trail.interval", TrailConfig, interval);
+        console_field!(app, "trail.spacing", TrailConfig, spacing);
+        console_field!(app, "trail.max_points", TrailConfig, max_points);
+    }
+}
+
+/// A breadcrumb trail drawn behind the player to help with finding the way back.
+#[derive(Resource, Debug, Clone)]
+pub struct TrailConfig {
+    pub enabled: bool,
+    /// Seconds between breadcrumbs while the player is moving.
+    pub interval: f32,
+    /// Distance the player can travel before a breadcrumb is dropped regardless of time.
+    pub spacing: f32,
+    /// Oldest breadcrumbs are dropped beyond this count.
+    pub max_points: usize,
+    pub color: Color,
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 0.5,
+            spacing: 2.0,
+            max_points: 200,
+            color: Color::from(tailwind::AMBER_400),
+        }
+    }
+}
+
+/// Recorded player positions, oldest first.
+#[derive(Resource, Debug, Default)]
+pub struct Breadcrumbs {
+    points: VecDeque<Vec3>,
+    since_last: f32,
+}
+
+fn toggle_trail(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<TrailConfig>,
+) {
+    if kb_input.any_just_pressed(bindings.world.trail.iter().copied()) {
+        config.enabled = !config.enabled;
+    }
+}
+
+fn record_breadcrumbs(
+    time: Res<Time>,
+    config: Res<TrailConfig>,
+    mut breadcrumbs: ResMut<Breadcrumbs>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let Ok(transform) = player.get_single() else {
+        return;
+    };
+
+    breadcrumbs.since_last += time.delta_seconds();
+
+    let position = transform.translation;
+    let distance = breadcrumbs
+        .points
+        .back()
+        .map_or(f32::INFINITY, |last| last.distance(position));
+
+    // Drop a breadcrumb every `interval` while moving, or sooner when moving fast.
+    let moved = distance > 0.01;
+    if distance < config.spacing && !(moved && breadcrumbs.since_last >= config.interval) {
+        return;
+    }
+
+    breadcrumbs.points.push_back(position);
+    breadcrumbs.since_last = 0.0;
+    while breadcrumbs.points.len() > config.max_points {
+        breadcrumbs.points.pop_front();
+    }
+}
+
+fn draw_breadcrumbs(
+    mut gizmos: Gizmos,
+    config: Res<TrailConfig>,
+    breadcrumbs: Res<Breadcrumbs>,
+    gravity: Res<Gravity>,
+) {
+    let count = breadcrumbs.points.len();
+    if count < 2 {
+        return;
+    }
+
+    let down = gravity.0.normalize_or_zero();
+
+    // Fade the trail out towards the oldest breadcrumbs.
+    gizmos.linestrip_gradient(breadcrumbs.points.iter().enumerate().map(|(i, point)| {
+        let alpha = (i + 1) as f32 / count as f32;
+        (
+            *point + down * TRAIL_GROUND_OFFSET,
+            config.color.with_alpha(alpha),
+        )
+    }));
+}
+
+fn clear_breadcrumbs(mut breadcrumbs: ResMut<Breadcrumbs>) {
+    *breadcrumbs = Breadcrumbs::default();
+}
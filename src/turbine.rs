@@ -1,94 +1,465 @@
+use std::ops::RangeInclusive;
+
 use avian3d::prelude::*;
+use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy::render::{
     render_asset::RenderAssetUsages,
     render_resource::{Extent3d, TextureDimension, TextureFormat},
 };
 
+use noise::{NoiseFn, Perlin};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::KeyBindings;
+use crate::hud::{HudElement, HudRegion};
+use crate::physics::{gravity_up, CcdConfig};
+use crate::player::Player;
+use crate::terrain::{Terrain, TerrainColliderTask};
+
+/// How far above the surface the ring raycasts start.
+const RING_RAYCAST_HEIGHT: f32 = 50.0;
+
+/// How far above the base of a tower the support raycast starts, so a base sunk into
+/// the ground still finds the surface.
+const SUPPORT_RAYCAST_HEIGHT: f32 = 1.0;
+
+/// Power a blade generates per radian per second it turns, in kilowatts.
+const BLADE_POWER: f32 = 1.0;
+
+/// Seed of the noise the wind drifts along.
+const WIND_NOISE_SEED: u32 = 7;
+
+/// The turbine model in the assets folder, for [`TurbineConfig::model`]. Its blades are
+/// the `Blade1` to `Blade3` nodes.
+pub const TURBINE_MODEL: &str = "models/turbine.gltf";
+
+/// Blade counts picked from when [`TurbineConfig::random_blade_count`] is set.
+const RANDOM_BLADE_COUNTS: RangeInclusive<usize> = 2..=5;
+
+/// Tints picked from when [`TurbineConfig::random_tint`] is set.
+const TURBINE_TINTS: [Color; 5] = [
+    Color::Srgba(css::WHITE),
+    Color::Srgba(css::LIGHT_SKY_BLUE),
+    Color::Srgba(css::PALE_GOLDENROD),
+    Color::Srgba(css::LIGHT_PINK),
+    Color::Srgba(css::PALE_GREEN),
+];
+
+/// Layout of the turbine ring dropped around the player.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TurbineRingConfig {
+    pub count: usize,
+    pub radius: f32,
+    /// Seed for the per-turbine randomization, so rings are reproducible.
+    pub seed: u64,
+    /// Seconds before a dropped turbine is removed again, or 0 to keep it.
+    pub lifetime: f32,
+}
+
+impl Default for TurbineRingConfig {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            radius: 12.0,
+            seed: 42,
+            lifetime: 0.0,
+        }
+    }
+}
+
+/// A turbine built from `TurbineConfig::model`, remembering how fast to spin the blades
+/// found in its scene.
+#[derive(Debug, Component)]
+pub struct TurbineModel {
+    pub rotation_speed: f32,
+}
+
+/// Marks every entity that makes up a wind turbine.
+#[derive(Debug, Component)]
+pub struct TurbinePart;
+
+/// The tower of a wind turbine, which is its root entity. The nacelle and blades are
+/// its children, or the model's scene when one is set.
+#[derive(Debug, Component)]
+pub struct Turbine {
+    pub tower_height: f32,
+}
+
+/// A turbine dropped in a ring around the player. Later rings, and later turbines within
+/// a ring, sort after earlier ones, so the order they were dropped in is kept.
+#[derive(Debug, Component, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DroppedTurbine {
+    ring: u64,
+    index: usize,
+}
+
+/// Removes the turbine, with all its parts, once the timer finishes.
+#[derive(Debug, Component)]
+pub struct Lifetime(pub Timer);
+
+/// A turbine that lost its support and was left to fall over.
+#[derive(Debug, Component)]
+pub struct Toppled;
+
+/// Turbine appearance and shape settings.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TurbineConfig {
+    pub material: TurbineMaterial,
+    pub shape: TurbineShape,
+    pub blade_count: usize,
+    /// Give each turbine in a ring a random number of blades instead of `blade_count`.
+    pub random_blade_count: bool,
+    /// Tint each turbine in a ring with a random color from a fixed palette.
+    pub random_tint: bool,
+    /// Spin about half the turbines in a ring the other way.
+    pub random_direction: bool,
+    /// Steepest ground, in degrees, a turbine stays standing on before it topples.
+    pub max_support_slope: f32,
+    /// Largest gap between the base of a turbine and the ground below it before the
+    /// turbine counts as unsupported.
+    pub max_support_gap: f32,
+    /// Most turbines that can exist at once. Dropping more removes the oldest first.
+    pub max_turbines: usize,
+    /// A glTF file from the assets folder to build turbines from, e.g. [`TURBINE_MODEL`],
+    /// instead of the primitive shapes. Its first scene is used, with the tower base at
+    /// the origin and the rotor facing +Z.
+    pub model: Option<String>,
+    /// Nodes in `model` whose names start with this are spun as blades, each around its
+    /// own origin.
+    pub model_blade_prefix: String,
+}
+
+impl Default for TurbineConfig {
+    fn default() -> Self {
+        Self {
+            material: TurbineMaterial::default(),
+            shape: TurbineShape::default(),
+            blade_count: 3,
+            random_blade_count: false,
+            random_tint: false,
+            random_direction: true,
+            max_support_slope: 40.0,
+            max_support_gap: 1.5,
+            max_turbines: 20,
+            model: None,
+            model_blade_prefix: "Blade".to_string(),
+        }
+    }
+}
+
+/// The wind turning the turbines. Blades spin fastest on turbines facing straight into
+/// it, and barely at all on ones facing away.
+#[derive(Resource, Debug, Clone)]
+pub struct Wind {
+    /// Where the wind blows towards. Turbines face +Z when spawned, so the default
+    /// blows straight into them.
+    pub direction: Vec3,
+    /// Blades on a turbine facing into the wind spin at their own speed times this.
+    /// Overwritten while `varies` is set.
+    pub strength: f32,
+    /// Let the direction and strength drift over time.
+    pub varies: bool,
+    /// Strength the drifting strength swings around.
+    pub mean_strength: f32,
+    /// How far the strength swings from the mean, as a fraction of it.
+    pub gustiness: f32,
+    /// Fastest the drifting direction turns, in radians per second.
+    pub max_turn_rate: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::NEG_Z,
+            strength: 1.0,
+            varies: true,
+            mean_strength: 1.0,
+            gustiness: 0.3,
+            max_turn_rate: 0.05,
+        }
+    }
+}
+
+/// Energy generated by all the turbines so far, in kilojoules. Only turning blades
+/// generate any, so it follows the wind.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TurbineEnergy {
+    pub total: f64,
+}
+
+#[derive(Component)]
+struct EnergyText;
+
+/// Dimensions of a turbine. Changes only apply to turbines spawned afterwards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TurbineShape {
+    pub tower_radius: f32,
+    pub tower_height: f32,
+    pub blade_length: f32,
+    /// Radius of each blade.
+    pub blade_thickness: f32,
+}
+
+impl Default for TurbineShape {
+    fn default() -> Self {
+        Self {
+            tower_radius: 0.3,
+            tower_height: 8.0,
+            blade_length: 4.0,
+            blade_thickness: 0.1,
+        }
+    }
+}
+
+/// What turbines are rendered with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum TurbineMaterial {
+    /// The colorful UV test pattern.
+    #[default]
+    Debug,
+    Solid(Color),
+    /// An image from the assets folder, e.g. `"textures/turbine.png"`.
+    Textured(String),
+}
+
+/// Assets shared between all turbines.
+#[derive(Resource, Debug, Default)]
+pub struct TurbineAssets {
+    /// One material per distinct `TurbineMaterial` and tint requested so far.
+    materials: Vec<(TurbineMaterial, Color, Handle<StandardMaterial>)>,
+    /// One scene per model path requested so far.
+    models: Vec<(String, Handle<Scene>)>,
+    /// One set of meshes per distinct `TurbineShape` requested so far.
+    meshes: Vec<(TurbineShape, TurbineMeshes)>,
+    /// The texture of `TurbineMaterial::Debug`, shared by all its tints.
+    debug_image: Option<Handle<Image>>,
+}
+
+/// The meshes of a primitive turbine with one `TurbineShape`.
+#[derive(Debug, Clone)]
+pub struct TurbineMeshes {
+    pub tower: Handle<Mesh>,
+    pub nacelle: Handle<Mesh>,
+    pub blade: Handle<Mesh>,
+}
+
+impl TurbineAssets {
+    /// Returns the meshes for a turbine with `shape`, creating them the first time
+    /// they're requested.
+    pub fn meshes(&mut self, shape: &TurbineShape, meshes: &mut Assets<Mesh>) -> TurbineMeshes {
+        if let Some((_, handles)) = self.meshes.iter().find(|(cached, _)| cached == shape) {
+            return handles.clone();
+        }
+
+        let handles = TurbineMeshes {
+            tower: meshes.add(Cylinder {
+                radius: shape.tower_radius,
+                half_height: shape.tower_height / 2.0,
+            }),
+            nacelle: meshes.add(Cuboid {
+                half_size: Vec3::new(0.5, 0.5, 1.0),
+            }),
+            blade: meshes.add(Cylinder {
+                radius: shape.blade_thickness,
+                half_height: shape.blade_length / 2.0,
+            }),
+        };
+        self.meshes.push((shape.clone(), handles.clone()));
+        handles
+    }
+
+    /// Returns the first scene of the glTF at `path`, starting the load the first time
+    /// it's requested.
+    pub fn model(&mut self, path: &str, asset_server: &AssetServer) -> Handle<Scene> {
+        if let Some((_, handle)) = self.models.iter().find(|(cached, _)| cached == path) {
+            return handle.clone();
+        }
+
+        let handle = asset_server.load(GltfAssetLabel::Scene(0).from_asset(path.to_string()));
+        self.models.push((path.to_string(), handle.clone()));
+        handle
+    }
+
+    /// Returns the material for `kind`, creating it the first time it's requested.
+    pub fn material(
+        &mut self,
+        kind: &TurbineMaterial,
+        materials: &mut Assets<StandardMaterial>,
+        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+    ) -> Handle<StandardMaterial> {
+        self.tinted_material(kind, Color::WHITE, materials, images, asset_server)
+    }
+
+    /// Returns the material for `kind` multiplied by `tint`, creating it the first time
+    /// it's requested.
+    pub fn tinted_material(
+        &mut self,
+        kind: &TurbineMaterial,
+        tint: Color,
+        materials: &mut Assets<StandardMaterial>,
+        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+    ) -> Handle<StandardMaterial> {
+        if let Some((_, _, handle)) = self
+            .materials
+            .iter()
+            .find(|(cached, cached_tint, _)| cached == kind && *cached_tint == tint)
+        {
+            return handle.clone();
+        }
+
+        let material = match kind {
+            TurbineMaterial::Debug => StandardMaterial {
+                base_color: tint,
+                base_color_texture: Some(
+                    self.debug_image
+                        .get_or_insert_with(|| images.add(uv_debug_texture()))
+                        .clone(),
+                ),
+                ..default()
+            },
+            TurbineMaterial::Solid(color) => {
+                let (color, tint) = (color.to_linear(), tint.to_linear());
+                StandardMaterial::from(Color::linear_rgba(
+                    color.red * tint.red,
+                    color.green * tint.green,
+                    color.blue * tint.blue,
+                    color.alpha * tint.alpha,
+                ))
+            }
+            TurbineMaterial::Textured(path) => StandardMaterial {
+                base_color: tint,
+                base_color_texture: Some(asset_server.load(path.clone())),
+                ..default()
+            },
+        };
+
+        let handle = materials.add(material);
+        self.materials.push((kind.clone(), tint, handle.clone()));
+        handle
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup_wind_turbines(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    config: Res<TurbineConfig>,
+    mut turbine_assets: ResMut<TurbineAssets>,
 ) {
+    let material =
+        turbine_assets.material(&config.material, &mut materials, &mut images, &asset_server);
+    let model = config
+        .model
+        .as_ref()
+        .map(|path| turbine_assets.model(path, &asset_server));
+    let turbine_meshes = turbine_assets.meshes(&config.shape, &mut meshes);
+
     // Spawn multiple wind turbines at different positions
     spawn_wind_turbine(
         &mut commands,
-        &mut meshes,
-        &mut materials,
-        &mut images,
-        Vec3::new(0.0, 0.0, 0.0),
+        &turbine_meshes,
+        material.clone(),
+        model.clone(),
+        Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
         1.0,
+        &config.shape,
+        config.blade_count,
     );
     spawn_wind_turbine(
         &mut commands,
-        &mut meshes,
-        &mut materials,
-        &mut images,
-        Vec3::new(3.0, 0.0, 10.0),
+        &turbine_meshes,
+        material.clone(),
+        model.clone(),
+        Transform::from_translation(Vec3::new(3.0, 0.0, 10.0)),
         1.2,
+        &config.shape,
+        config.blade_count,
     );
     spawn_wind_turbine(
         &mut commands,
-        &mut meshes,
-        &mut materials,
-        &mut images,
-        Vec3::new(-3.0, 0.0, -10.0),
+        &turbine_meshes,
+        material,
+        model.clone(),
+        Transform::from_translation(Vec3::new(-3.0, 0.0, -10.0)),
         0.8,
+        &config.shape,
+        config.blade_count,
     );
 }
 
-fn spawn_wind_turbine(
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_wind_turbine(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    images: &mut ResMut<Assets<Image>>,
-    position: Vec3,
+    meshes: &TurbineMeshes,
+    material: Handle<StandardMaterial>,
+    model: Option<Handle<Scene>>,
+    base: Transform,
     rotation_speed: f32,
-) {
-    let debug_material = materials.add(StandardMaterial {
-        base_color_texture: Some(images.add(uv_debug_texture())),
-        ..default()
-    });
+    shape: &TurbineShape,
+    blade_count: usize,
+) -> Entity {
+    if let Some(model) = model {
+        return spawn_wind_turbine_model(commands, model, base, rotation_speed, shape);
+    }
 
-    // Tower (Cylinder)
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(Cylinder {
-                radius: 0.3,
-                half_height: 4.0,
+    // Tower (Cylinder), standing on `base` along its up axis. Everything else is
+    // positioned relative to its center.
+    let tower = commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.tower.clone(),
+                material: material.clone(),
+                transform: Transform {
+                    translation: base.translation
+                        + base.rotation * Vec3::new(0.0, shape.tower_height / 2.0, 0.0),
+                    ..base
+                },
                 ..Default::default()
-            })),
-            material: debug_material.clone(),
-            transform: Transform::from_translation(position + Vec3::new(0.0, 4.0, 0.0)),
-            ..Default::default()
-        },
-        RigidBody::Static,
-        Collider::cylinder(0.3, 8.0)
-    ));
+            },
+            RigidBody::Static,
+            Collider::cylinder(shape.tower_radius, shape.tower_height),
+            Turbine {
+                tower_height: shape.tower_height,
+            },
+            TurbinePart,
+        ))
+        .id();
 
-    // Nacelle (Cube)
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cuboid {
-            half_size: Vec3::new(0.5, 0.5, 1.0),
-        })),
-        material: debug_material.clone(),
-        transform: Transform {
-            translation: position + Vec3::new(0.0, 8.5, 0.0),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
+    // Nacelle (Cube), sitting on top of the tower.
+    let nacelle_position = Vec3::new(0.0, shape.tower_height / 2.0 + 0.5, 0.0);
+    let nacelle = commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.nacelle.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(nacelle_position),
+                ..Default::default()
+            },
+            // Without a rigid body of its own the collider is part of the tower's.
+            Collider::cuboid(1.0, 1.0, 2.0),
+            TurbinePart,
+        ))
+        .id();
+    commands.entity(tower).add_child(nacelle);
 
     // Blades (Cylinder)
-    let blade_length = 4.0;
-    let blade_thickness = 0.1;
-    let blade_axis_position = position + Vec3::new(0.0, 8.5, 1.1);
+    let blade_axis_position = nacelle_position + Vec3::new(0.0, 0.0, 1.1);
 
-    for i in 0..3 {
-        let angle = (i as f32) * (2.0 * std::f32::consts::PI / 3.0);
+    for i in 0..blade_count {
+        let angle = (i as f32) * (2.0 * std::f32::consts::PI / blade_count as f32);
 
-        let blade_offset = Vec3::new(0.0, blade_length / 2.0, 0.0); // Offset the blade by half its length
+        let blade_offset = Vec3::new(0.0, shape.blade_length / 2.0, 0.0); // Offset the blade by half its length
         let rotated_offset = Quat::from_rotation_z(angle) * blade_offset; // Apply rotation to the offset
 
         let blade_transform = Transform {
@@ -97,43 +468,468 @@ fn spawn_wind_turbine(
             ..Default::default()
         };
 
-        commands
+        let blade = commands
             .spawn(PbrBundle {
-                mesh: meshes.add(Mesh::from(Cylinder {
-                    radius: blade_thickness,
-                    half_height: blade_length / 2.0,
-                    ..Default::default()
-                })),
-                material: debug_material.clone(),
+                mesh: meshes.blade.clone(),
+                material: material.clone(),
                 transform: blade_transform,
                 ..Default::default()
             })
-            .insert(Blade) // Insert Blade component
-            .insert(RotationSpeed(rotation_speed)); // Assign rotation speed to the blade
+            .insert((
+                Blade,
+                BladeHub {
+                    center: blade_axis_position,
+                    distance: shape.blade_length / 2.0,
+                },
+                TurbinePart,
+            ))
+            // Kinematic, so the collider follows the blade as `rotate_blades` turns it.
+            // It's moved by its transform rather than a velocity, so it blocks the player
+            // but doesn't push them.
+            .insert((
+                RigidBody::Kinematic,
+                Collider::cylinder(shape.blade_thickness, shape.blade_length),
+            ))
+            .insert(RotationSpeed(rotation_speed)) // Assign rotation speed to the blade
+            .id();
+        commands.entity(tower).add_child(blade);
+    }
+
+    tower
+}
+
+/// Spawns a turbine from a glTF scene. The tower collider still comes from `shape`, and
+/// the blade nodes are tagged by `tag_turbine_model_blades` once the scene has spawned.
+fn spawn_wind_turbine_model(
+    commands: &mut Commands,
+    model: Handle<Scene>,
+    base: Transform,
+    rotation_speed: f32,
+    shape: &TurbineShape,
+) -> Entity {
+    // The tower entity sits at the middle of the tower like the primitive one, so
+    // toppling works the same, and the scene is moved down to stand on `base`.
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform {
+                translation: base.translation
+                    + base.rotation * Vec3::new(0.0, shape.tower_height / 2.0, 0.0),
+                ..base
+            }),
+            RigidBody::Static,
+            Collider::cylinder(shape.tower_radius, shape.tower_height),
+            Turbine {
+                tower_height: shape.tower_height,
+            },
+            TurbineModel { rotation_speed },
+            TurbinePart,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SceneBundle {
+                scene: model,
+                transform: Transform::from_xyz(0.0, -shape.tower_height / 2.0, 0.0),
+                ..default()
+            });
+        })
+        .id()
+}
+
+/// Turns the blade nodes of newly spawned turbine models into blades `rotate_blades`
+/// spins, by matching their names against `TurbineConfig::model_blade_prefix`.
+///
+/// glTF mesh primitives are spawned as children of their node, named after the mesh, so
+/// entities with a mesh are skipped. Otherwise a blade mesh named like the node would
+/// spin a second time inside its already turning node.
+pub fn tag_turbine_model_blades(
+    mut commands: Commands,
+    config: Res<TurbineConfig>,
+    nodes: Query<(Entity, &Name, &Transform), (Added<Name>, Without<Handle<Mesh>>)>,
+    parents: Query<&Parent>,
+    models: Query<&TurbineModel>,
+) {
+    for (entity, name, transform) in nodes.iter() {
+        if !name.as_str().starts_with(&config.model_blade_prefix) {
+            continue;
+        }
+        let Some(model) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| models.get(ancestor).ok())
+        else {
+            continue;
+        };
+
+        // The node's origin is the hub, so it turns in place.
+        commands.entity(entity).insert((
+            Blade,
+            BladeHub {
+                center: transform.translation,
+                distance: 0.0,
+            },
+            RotationSpeed(model.rotation_speed),
+        ));
+    }
+}
+
+/// Drops `count` evenly spaced turbines on the surface in a ring around the player.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_turbine_ring(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<TurbineRingConfig>,
+    turbine_config: Res<TurbineConfig>,
+    mut turbine_assets: ResMut<TurbineAssets>,
+    gravity: Res<Gravity>,
+    spatial_query: SpatialQuery,
+    player: Query<(Entity, &Transform), With<Player>>,
+    turbine_parts: Query<Entity, With<TurbinePart>>,
+    mut rings_spawned: Local<u64>,
+) {
+    if !kb_input.any_just_pressed(bindings.world.turbine_ring.iter().copied()) {
+        return;
+    }
+
+    let Ok((player, player_transform)) = player.get_single() else {
+        return;
+    };
+
+    // Lay the ring out perpendicular to gravity and cast down onto the surface, ignoring
+    // the player and turbines that are already standing there.
+    let down = Dir3::new(gravity.0).unwrap_or(Dir3::NEG_Y);
+    let ring_rotation = Quat::from_rotation_arc(Vec3::NEG_Y, *down);
+    let filter = SpatialQueryFilter::default()
+        .with_excluded_entities(std::iter::once(player).chain(turbine_parts.iter()));
+
+    // Each ring gets its own seed so they differ, while runs stay reproducible.
+    let ring = *rings_spawned;
+    let mut rng = StdRng::seed_from_u64(config.seed.wrapping_add(ring));
+    *rings_spawned += 1;
+
+    let turbine_meshes = turbine_assets.meshes(&turbine_config.shape, &mut meshes);
+    for i in 0..config.count {
+        let angle = i as f32 * std::f32::consts::TAU / config.count as f32;
+        let offset = ring_rotation * Vec3::new(angle.cos(), 0.0, angle.sin()) * config.radius;
+        let origin = player_transform.translation + offset - *down * RING_RAYCAST_HEIGHT;
+
+        // Draw before raycasting so a miss doesn't shift the values of later turbines.
+        // Everything is drawn even when unused, so toggling one option doesn't change
+        // the others.
+        let rotation_speed = rng.gen_range(0.5..1.5);
+        let random_blade_count = rng.gen_range(RANDOM_BLADE_COUNTS);
+        let random_tint = TURBINE_TINTS[rng.gen_range(0..TURBINE_TINTS.len())];
+        let random_reversed = rng.gen_bool(0.5);
+
+        let Some(hit) = spatial_query.cast_ray(
+            origin,
+            down,
+            RING_RAYCAST_HEIGHT * 2.0,
+            true,
+            filter.clone(),
+        ) else {
+            continue;
+        };
+
+        let blade_count = if turbine_config.random_blade_count {
+            random_blade_count
+        } else {
+            turbine_config.blade_count
+        };
+        let rotation_speed = if turbine_config.random_direction && random_reversed {
+            -rotation_speed
+        } else {
+            rotation_speed
+        };
+        let tint = if turbine_config.random_tint {
+            random_tint
+        } else {
+            Color::WHITE
+        };
+        let material = turbine_assets.tinted_material(
+            &turbine_config.material,
+            tint,
+            &mut materials,
+            &mut images,
+            &asset_server,
+        );
+
+        let model = turbine_config
+            .model
+            .as_ref()
+            .map(|path| turbine_assets.model(path, &asset_server));
+        let turbine = spawn_wind_turbine(
+            &mut commands,
+            &turbine_meshes,
+            material,
+            model,
+            // Stand square to the surface rather than upright against gravity.
+            Transform::from_translation(origin + *down * hit.time_of_impact)
+                .with_rotation(Quat::from_rotation_arc(Vec3::Y, hit.normal)),
+            rotation_speed,
+            &turbine_config.shape,
+            blade_count,
+        );
+        commands
+            .entity(turbine)
+            .insert(DroppedTurbine { ring, index: i });
+        if config.lifetime > 0.0 {
+            commands
+                .entity(turbine)
+                .insert(Lifetime(Timer::from_seconds(
+                    config.lifetime,
+                    TimerMode::Once,
+                )));
+        }
+    }
+}
+
+/// Removes the oldest turbines while there are more than [`TurbineConfig::max_turbines`].
+/// The ones placed at startup are older than any dropped ones.
+pub fn limit_turbine_count(
+    mut commands: Commands,
+    config: Res<TurbineConfig>,
+    turbines: Query<(Entity, Option<&DroppedTurbine>), With<Turbine>>,
+) {
+    let excess = turbines.iter().len().saturating_sub(config.max_turbines);
+    if excess == 0 {
+        return;
+    }
+
+    let mut turbines: Vec<_> = turbines.iter().collect();
+    turbines.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (turbine, _) in turbines.into_iter().take(excess) {
+        commands.entity(turbine).despawn_recursive();
+    }
+}
+
+/// Removes the most recently dropped turbine that's still around.
+pub fn remove_last_dropped_turbine(
+    mut commands: Commands,
+    kb_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    turbines: Query<(Entity, &DroppedTurbine)>,
+) {
+    if !kb_input.any_just_pressed(bindings.world.remove_turbine.iter().copied()) {
+        return;
+    }
+
+    if let Some((turbine, _)) = turbines.iter().max_by(|(_, a), (_, b)| a.cmp(b)) {
+        commands.entity(turbine).despawn_recursive();
+    }
+}
+
+pub fn despawn_expired_turbines(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut turbines: Query<(Entity, &mut Lifetime)>,
+) {
+    for (turbine, mut lifetime) in turbines.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(turbine).despawn_recursive();
+        }
+    }
+}
+
+pub fn despawn_all_turbines(mut commands: Commands, turbines: Query<Entity, With<Turbine>>) {
+    for turbine in turbines.iter() {
+        commands.entity(turbine).despawn_recursive();
+    }
+}
+
+/// Lets standing turbines fall over once the ground below them is gone or too steep.
+/// Nothing is checked until every loaded terrain chunk has a collider in the physics world.
+#[allow(clippy::too_many_arguments)]
+pub fn topple_unsupported_turbines(
+    mut commands: Commands,
+    config: Res<TurbineConfig>,
+    ccd: Res<CcdConfig>,
+    gravity: Res<Gravity>,
+    spatial_query: SpatialQuery,
+    turbines: Query<(Entity, &Turbine, &GlobalTransform), Without<Toppled>>,
+    turbine_parts: Query<Entity, With<TurbinePart>>,
+    children: Query<&Children>,
+    blades: Query<(), With<Blade>>,
+    terrain: Query<(Has<TerrainColliderTask>, Has<Collider>, Has<ColliderAabb>), With<Terrain>>,
+) {
+    // Wait until the terrain is there for the turbines to stand on. Chunks can still be
+    // building their collider, and a new collider only gets its AABB once a physics
+    // step has seen it, which is also when it's added to the spatial query pipeline.
+    let terrain_ready = !terrain.is_empty()
+        && terrain
+            .iter()
+            .all(|(building, collider, aabb)| !building && (!collider || aabb));
+    if !terrain_ready {
+        return;
+    }
+
+    let down = Dir3::new(gravity.0).unwrap_or(Dir3::NEG_Y);
+    let max_slope = config.max_support_slope.to_radians();
+    let filter = SpatialQueryFilter::default().with_excluded_entities(turbine_parts.iter());
+
+    for (entity, turbine, transform) in turbines.iter() {
+        // Towers can lean, so find the base along the tower rather than along gravity.
+        let base = transform.translation() - *transform.up() * (turbine.tower_height / 2.0);
+        let origin = base - *down * SUPPORT_RAYCAST_HEIGHT;
+
+        let supported = spatial_query
+            .cast_ray(
+                origin,
+                down,
+                SUPPORT_RAYCAST_HEIGHT + config.max_support_gap,
+                true,
+                filter.clone(),
+            )
+            .is_some_and(|hit| hit.normal.angle_between(-*down) <= max_slope);
+        if supported {
+            continue;
+        }
+
+        let turbine_blades = children
+            .iter_descendants(entity)
+            .filter(|descendant| blades.contains(*descendant));
+        topple_turbine(&mut commands, entity, turbine_blades, ccd.turbines);
+    }
+}
+
+/// Makes a standing turbine dynamic, so it falls over. It's the first time the tower
+/// moves, so that's when it gets swept CCD, if `ccd` is set.
+///
+/// The `blades` lose their kinematic bodies, so their colliders join the tower's body
+/// and fall with it, instead of holding it up from inside the nacelle.
+pub fn topple_turbine(
+    commands: &mut Commands,
+    turbine: Entity,
+    blades: impl IntoIterator<Item = Entity>,
+    ccd: bool,
+) {
+    for blade in blades {
+        commands.entity(blade).remove::<RigidBody>();
+    }
+
+    let mut turbine = commands.entity(turbine);
+    turbine.insert((RigidBody::Dynamic, Toppled));
+    if ccd {
+        turbine.insert(SweptCcd::default());
     }
 }
 
 #[derive(Debug, Component)]
 pub struct Blade;
 
+/// Where a blade turns around. The center is shared by all the blades of a turbine.
+#[derive(Debug, Component)]
+pub struct BladeHub {
+    /// Relative to the tower.
+    center: Vec3,
+    /// From the center to the middle of the blade.
+    distance: f32,
+}
+
+/// How fast a blade turns in radians per second, before the wind. Negative speeds turn
+/// the other way.
 #[derive(Component)]
 pub struct RotationSpeed(f32);
 
+/// Smooth noise over time for [`vary_wind`], so the wind wanders rather than jitters.
+pub struct WindNoise(Perlin);
+
+impl Default for WindNoise {
+    fn default() -> Self {
+        Self(Perlin::new(WIND_NOISE_SEED))
+    }
+}
+
+/// Slowly turns the wind and lets it gust, when [`Wind::varies`] is set.
+pub fn vary_wind(
+    time: Res<Time>,
+    gravity: Res<Gravity>,
+    mut wind: ResMut<Wind>,
+    noise: Local<WindNoise>,
+) {
+    if !wind.varies {
+        return;
+    }
+
+    let perlin = &noise.0;
+    let t = time.elapsed_seconds_f64();
+    let turn = perlin.get([t * 0.05, 0.5]) as f32;
+    let gust = perlin.get([t * 0.2, 10.5]) as f32;
+
+    let up = gravity_up(&gravity);
+    let turned = Quat::from_axis_angle(*up, turn * wind.max_turn_rate * time.delta_seconds())
+        * wind.direction;
+    // Keep the wind blowing across the ground.
+    let across = turned - *up * turned.dot(*up);
+    wind.direction = across.try_normalize().unwrap_or(wind.direction);
+    wind.strength = (wind.mean_strength * (1.0 + wind.gustiness * gust)).max(0.0);
+}
+
 pub fn rotate_blades(
     time: Res<Time>,
-    mut query: Query<(&RotationSpeed, &mut Transform), With<Blade>>,
+    wind: Res<Wind>,
+    mut energy: ResMut<TurbineEnergy>,
+    mut query: Query<(Entity, &RotationSpeed, &BladeHub, &mut Transform), With<Blade>>,
+    parents: Query<&Parent>,
+    towers: Query<&GlobalTransform, (With<Turbine>, Without<Toppled>)>,
 ) {
-    for (rotation_speed, mut transform) in query.iter_mut() {
-        let delta_rotation = Quat::from_rotation_z(time.delta_seconds() * rotation_speed.0);
+    let wind_direction = wind.direction.normalize_or_zero();
+
+    for (entity, rotation_speed, hub, mut transform) in query.iter_mut() {
+        // Blades from a model can be nested deeper than the tower's children. A fallen
+        // turbine stops turning.
+        let Some(tower) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| towers.get(ancestor).ok())
+        else {
+            continue;
+        };
+
+        // The blades are on the tower's +Z side, so the turbine faces the wind when that
+        // points against it. Facing away still leaves a little spin.
+        let facing = tower.back().dot(-wind_direction).max(0.0);
+        let speed = rotation_speed.0 * wind.strength * (0.1 + 0.9 * facing);
 
-        // Calculate the pivot point (the end of the blade)
-        let pivot = transform.translation - transform.rotation * Vec3::new(0.0, 2.0, 0.0);
+        let delta_rotation = Quat::from_rotation_z(time.delta_seconds() * speed);
+        energy.total += (speed.abs() * BLADE_POWER * time.delta_seconds()) as f64;
 
-        // Rotate around the pivot point
-        transform.rotate_around(pivot, delta_rotation);
+        // Turn the blade, then place it back out from the hub along its new rotation. The
+        // position is rebuilt rather than rotated, so rounding errors can't build up and
+        // move the blades apart.
+        transform.rotation = (delta_rotation * transform.rotation).normalize();
+        transform.translation = hub.center + transform.rotation * Vec3::new(0.0, hub.distance, 0.0);
     }
 }
 
+pub fn spawn_energy_text(mut commands: Commands) {
+    commands.spawn((
+        EnergyText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                ..default()
+            },
+        ),
+        HudElement::new(HudRegion::TopRight),
+    ));
+}
+
+pub fn update_energy_text(
+    energy: Res<TurbineEnergy>,
+    mut text: Query<&mut Text, With<EnergyText>>,
+) {
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!("Energy: {:.1} kJ", energy.total);
+    }
+}
+
+pub fn reset_turbine_energy(mut energy: ResMut<TurbineEnergy>) {
+    *energy = TurbineEnergy::default();
+}
+
 /// Creates a colorful test pattern
 pub fn uv_debug_texture() -> Image {
     const TEXTURE_SIZE: usize = 8;
@@ -162,3 +958,244 @@ pub fn uv_debug_texture() -> Image {
         RenderAssetUsages::RENDER_WORLD,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::terrain::{stream_terrain_chunks, swap_terrain_collider, TerrainConfig};
+
+    #[test]
+    fn model_blades_are_tagged_by_name() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<TurbineConfig>()
+            .add_systems(Update, tag_turbine_model_blades);
+
+        // The model's nodes sit below the scene root, which is the tower's child.
+        let world = app.world_mut();
+        let tower = world
+            .spawn(TurbineModel {
+                rotation_speed: 2.0,
+            })
+            .id();
+        let scene = world.spawn_empty().set_parent(tower).id();
+        let nacelle = world
+            .spawn((Name::new("Nacelle"), Transform::default()))
+            .set_parent(scene)
+            .id();
+        let blade = world
+            .spawn((Name::new("Blade1"), Transform::from_xyz(0.0, 0.0, 1.1)))
+            .set_parent(nacelle)
+            .id();
+        // The primitive child bevy_gltf spawns for the node's mesh, named after the mesh.
+        let primitive = world
+            .spawn((
+                Name::new("Blade"),
+                Transform::default(),
+                Handle::<Mesh>::default(),
+            ))
+            .set_parent(blade)
+            .id();
+
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<Blade>(nacelle).is_none());
+        assert!(world.get::<Blade>(blade).is_some());
+        assert!(world.get::<Blade>(primitive).is_none());
+        assert_eq!(
+            world.get::<RotationSpeed>(blade).map(|speed| speed.0),
+            Some(2.0)
+        );
+        let hub = world.get::<BladeHub>(blade).unwrap();
+        assert_eq!(hub.center, Vec3::new(0.0, 0.0, 1.1));
+        assert_eq!(hub.distance, 0.0);
+    }
+
+    /// Drops a toppled turbine from high above a slab much thinner than the distance it
+    /// falls in one physics step, and checks it comes to rest on top.
+    #[test]
+    fn dropped_turbine_lands_on_thin_ground() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 64.0,
+        )))
+        .insert_resource(Gravity(Vec3::NEG_Y * 100.0));
+        app.finish();
+        app.cleanup();
+
+        let turbine = app.world_mut().run_system_once(
+            |mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>| {
+                commands.spawn((
+                    TransformBundle::default(),
+                    RigidBody::Static,
+                    Collider::cuboid(100.0, 0.1, 100.0),
+                ));
+
+                let turbine_meshes =
+                    TurbineAssets::default().meshes(&TurbineShape::default(), &mut meshes);
+                // No blades, so only the tower and nacelle fall.
+                let turbine = spawn_wind_turbine(
+                    &mut commands,
+                    &turbine_meshes,
+                    Handle::default(),
+                    None,
+                    Transform::from_xyz(0.0, 100.0, 0.0),
+                    1.0,
+                    &TurbineShape::default(),
+                    0,
+                );
+                topple_turbine(&mut commands, turbine, [], true);
+                turbine
+            },
+        );
+
+        for _ in 0..300 {
+            app.update();
+        }
+
+        // Whichever way up it landed, the bottom of the tower rests on top of the slab,
+        // rather than part way through it.
+        let world = app.world();
+        let lowest = world.get::<ColliderAabb>(turbine).unwrap().min.y;
+        assert!(
+            (lowest - 0.05).abs() < 0.1,
+            "the bottom of the tower ended up at {lowest}, not on the slab"
+        );
+        let velocity = world.get::<LinearVelocity>(turbine).unwrap();
+        assert!(
+            velocity.length() < 0.1,
+            "the turbine is still moving at {}",
+            velocity.0
+        );
+    }
+
+    /// The terrain's colliders are built in the background after launch. The turbines
+    /// placed at startup have to wait for them instead of toppling over nothing.
+    #[test]
+    fn startup_turbines_wait_for_the_terrain() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 64.0,
+        )))
+        .init_resource::<TerrainConfig>()
+        .init_resource::<TurbineConfig>()
+        .init_resource::<TurbineAssets>()
+        .init_resource::<CcdConfig>()
+        .add_systems(Startup, (setup_wind_turbines, stream_terrain_chunks))
+        .add_systems(
+            Update,
+            (swap_terrain_collider, topple_unsupported_turbines).chain(),
+        );
+        app.finish();
+        app.cleanup();
+
+        // Give the background tasks time to build every collider, then let physics
+        // pick them up.
+        for _ in 0..1000 {
+            app.update();
+            let world = app.world_mut();
+            let building = world
+                .query_filtered::<(), With<TerrainColliderTask>>()
+                .iter(world)
+                .count();
+            if building == 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        let colliders = world
+            .query_filtered::<(), (With<Terrain>, With<Collider>)>()
+            .iter(world)
+            .count();
+        assert!(colliders > 0, "no terrain collider was built");
+        assert_eq!(world.query::<&Turbine>().iter(world).count(), 3);
+        let toppled = world
+            .query_filtered::<(), With<Toppled>>()
+            .iter(world)
+            .count();
+        assert_eq!(toppled, 0);
+    }
+
+    #[test]
+    fn turbine_meshes_are_shared_between_turbines_of_one_shape() {
+        let mut assets = TurbineAssets::default();
+        let mut meshes = Assets::<Mesh>::default();
+        let shape = TurbineShape::default();
+
+        let first = assets.meshes(&shape, &mut meshes);
+        let second = assets.meshes(&shape, &mut meshes);
+        assert_eq!(first.tower, second.tower);
+        assert_eq!(first.blade, second.blade);
+        assert_eq!(meshes.len(), 3);
+
+        let taller = TurbineShape {
+            tower_height: 12.0,
+            ..shape
+        };
+        assert_ne!(assets.meshes(&taller, &mut meshes).tower, first.tower);
+        assert_eq!(meshes.len(), 6);
+    }
+
+    #[test]
+    fn debug_material_tints_share_one_image() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>()
+            .init_resource::<TurbineAssets>();
+
+        app.world_mut().run_system_once(
+            |mut assets: ResMut<TurbineAssets>,
+             mut materials: ResMut<Assets<StandardMaterial>>,
+             mut images: ResMut<Assets<Image>>,
+             asset_server: Res<AssetServer>| {
+                for tint in TURBINE_TINTS {
+                    assets.tinted_material(
+                        &TurbineMaterial::Debug,
+                        tint,
+                        &mut materials,
+                        &mut images,
+                        &asset_server,
+                    );
+                }
+            },
+        );
+
+        let world = app.world();
+        assert_eq!(
+            world.resource::<Assets<StandardMaterial>>().len(),
+            TURBINE_TINTS.len()
+        );
+        assert_eq!(world.resource::<Assets<Image>>().len(), 1);
+    }
+}